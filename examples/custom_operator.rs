@@ -4,7 +4,7 @@ use metropolis::MetropolisBox;
 use mole::prelude::*;
 use ndarray::{Array1, Array2, Axis, Ix2};
 use ndarray_linalg::Norm;
-use operator::{KineticEnergy, LocalOperator, OperatorValue};
+use operator::{KineticEnergy, LocalOperator, OperatorSum, OperatorValue};
 use rand::{SeedableRng, StdRng};
 use util::operators;
 use wavefunction_traits::{Differentiate, Function};
@@ -26,37 +26,29 @@ impl Log for Logger {
     }
 }
 
-// Create a struct to hold Hamiltonian parameters
-struct HarmonicHamiltonian {
+// Create a struct to hold the potential parameters
+struct HarmonicPotential {
     // Harmonic oscillator potential is parametrized by natural frequency
     frequency: f64,
-    // Kinetic energy operator always has the same form
-    t: KineticEnergy,
 }
 
-impl HarmonicHamiltonian {
+impl HarmonicPotential {
     pub fn new(frequency: f64) -> Self {
-        Self {
-            t: KineticEnergy::new(),
-            frequency,
-        }
+        Self { frequency }
     }
 }
 
-// All observables must implement the Operator<T> trait
+// All observables must implement the LocalOperator<T> trait
 // T is the type parameter of the wave function.
-impl<T> LocalOperator<T> for HarmonicHamiltonian
+impl<T> LocalOperator<T> for HarmonicPotential
 where
-    T: Function<f64, D = Ix2> + Differentiate<D = Ix2>,
+    T: Function<f64, D = Ix2>,
 {
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
-        // Kinetic energy
-        let ke = self.t.act_on(wf, cfg)?;
         // Potential energy: V = 0.5*m*omega^2*|x|^2
-        let pe = OperatorValue::Scalar(
+        Ok(OperatorValue::Scalar(
             0.5 * self.frequency.powi(2) * cfg.norm_l2().powi(2) * wf.value(cfg)?,
-        );
-        Ok(&ke + &pe)
+        ))
     }
 }
 
@@ -105,8 +97,8 @@ fn main() {
 
     let metrop = MetropolisBox::from_rng(1.0, StdRng::from_seed([0; 32]));
 
-    // Construct our custom operator
-    let hamiltonian = HarmonicHamiltonian::new(omega);
+    // Construct the Hamiltonian from the kinetic energy and our custom potential
+    let hamiltonian = OperatorSum::new(KineticEnergy::new(), HarmonicPotential::new(omega));
 
     let obs = operators! {
         "Energy" => hamiltonian
@@ -82,8 +82,8 @@ impl Optimize for GaussianWaveFunction {
         self.params += deltap;
     }
 
-    fn parameters(&self) -> &Array1<f64> {
-        &self.params
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
     }
 
     fn num_parameters(&self) -> usize {
@@ -91,63 +91,6 @@ impl Optimize for GaussianWaveFunction {
     }
 }
 
-#[derive(Clone)]
-struct STO {
-    pars: Array1<f64>,
-}
-
-impl STO {
-    pub fn new(alpha: f64) -> Self {
-        Self {
-            pars: array![alpha],
-        }
-    }
-}
-impl Function<f64> for STO {
-    type D = Ix2;
-    fn value(&self, x: &Array2<f64>) -> Result<f64> {
-        let alpha = self.pars[0];
-        Ok(f64::exp(-alpha * x.norm_l2()))
-    }
-}
-
-impl Differentiate for STO {
-    type D = Ix2;
-    fn gradient(&self, x: &Array2<f64>) -> Result<Array2<f64>> {
-        let alpha = self.pars[0];
-        Ok(-alpha * self.value(x)? / x.norm_l2() * x)
-    }
-
-    fn laplacian(&self, x: &Array2<f64>) -> Result<f64> {
-        let alpha = self.pars[0];
-        Ok(alpha * self.value(x)? / x.norm_l2() * (alpha * x.norm_l2() - 2.0))
-    }
-}
-
-impl Optimize for STO {
-    fn parameter_gradient(&self, x: &Array2<f64>) -> Result<Array1<f64>> {
-        Ok(array![-x.norm_l2() * self.value(x)?])
-    }
-
-    fn update_parameters(&mut self, deltap: &Array1<f64>) {
-        self.pars += deltap[0];
-    }
-
-    fn num_parameters(&self) -> usize {
-        1
-    }
-
-    fn parameters(&self) -> &Array1<f64> {
-        &self.pars
-    }
-}
-
-impl WaveFunction for STO {
-    fn num_electrons(&self) -> usize {
-        1
-    }
-}
-
 static ITERS: usize = 100;
 static TOTAL_SAMPLES: usize = 5000;
 static BLOCK_SIZE: usize = 10;
@@ -155,7 +98,6 @@ static BLOCK_SIZE: usize = 10;
 fn main() {
     // Build wave function
     let ansatz = GaussianWaveFunction::new(1.0);
-    //let ansatz = STO::new(0.8);
 
     let metrop = MetropolisDiffuse::from_rng(0.1, StdRng::from_seed([0; 32]));
 
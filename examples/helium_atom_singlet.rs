@@ -108,8 +108,8 @@ impl Optimize for HeliumAtomWaveFunction {
         self.params += deltap;
     }
 
-    fn parameters(&self) -> &Array1<f64> {
-        &self.params
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
     }
 
     fn num_parameters(&self) -> usize {
@@ -132,11 +132,11 @@ fn main() {
     let (energies_sr, errors_sr) = optimize_wave_function(
         &ion_pos,
         wave_function.clone(),
-        StochasticReconfiguration::new(100_000.0),
+        StochasticReconfiguration::new(1.0),
     );
     println!("\nSTEEPEST DESCENT");
     let (energies_sd, errors_sd) =
-        optimize_wave_function(&ion_pos, wave_function.clone(), SteepestDescent::new(1e-5));
+        optimize_wave_function(&ion_pos, wave_function.clone(), SteepestDescent::new(0.1));
 
     // Plot the results
     plot_results(
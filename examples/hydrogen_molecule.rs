@@ -3,8 +3,7 @@ use std::collections::HashMap;
 #[macro_use]
 extern crate itertools;
 use gnuplot::{AxesCommon, Caption, Color, Figure, FillAlpha};
-use ndarray::{array, s, Array1, Array2, Ix2};
-use ndarray_linalg::Norm;
+use ndarray::{array, Array1, Array2, Ix2};
 use rand::{SeedableRng, StdRng};
 
 #[allow(unused_imports)]
@@ -30,53 +29,44 @@ impl Log for Logger {
     }
 }
 
-#[derive(Clone)]
-struct STO {
-    alpha: f64,
-}
-
-impl STO {
-    pub fn new(alpha: f64) -> Self {
-        Self { alpha }
-    }
-
-    pub fn value(&self, x: &Array1<f64>) -> f64 {
-        f64::exp(-self.alpha * x.norm_l2())
-    }
-
-    pub fn gradient(&self, x: &Array1<f64>) -> Array1<f64> {
-        -self.alpha * self.value(x) / x.norm_l2() * x
-    }
-
-    pub fn laplacian(&self, x: &Array1<f64>) -> f64 {
-        self.alpha * self.value(x) / x.norm_l2() * (self.alpha * x.norm_l2() - 2.0)
-    }
-
-    pub fn parameter_gradient(&self, x: &Array1<f64>) -> f64 {
-        -x.norm_l2() * self.value(x)
-    }
-
-    pub fn update_parameters(&mut self, deltap: f64) {
-        self.alpha += deltap;
-    }
-}
-
+// Symmetric product $\psi = \phi_A(\mathbf{r}_1)\phi_B(\mathbf{r}_2) + \phi_B(\mathbf{r}_1)\phi_A(\mathbf{r}_2)$
+// of 1s Slater orbitals on both ions, with a shared exponent as parameter
 #[derive(Clone)]
 struct HydrogenMoleculeWaveFunction {
-    nuclear_separation: f64,
+    ion_pos: Array2<f64>,
     params: Array1<f64>,
-    phi: STO,
+    orbitals: Vec<SlaterOrbital>,
 }
 
 impl HydrogenMoleculeWaveFunction {
-    pub fn new(nuclear_separation: f64, params: Array1<f64>) -> Self {
-        let alpha = params[0];
+    pub fn new(ion_pos: Array2<f64>, params: Array1<f64>) -> Self {
+        let orbitals = Self::orbitals(&ion_pos, params[0]);
         Self {
-            nuclear_separation,
+            ion_pos,
             params,
-            phi: STO::new(alpha),
+            orbitals,
         }
     }
+
+    fn orbitals(ion_pos: &Array2<f64>, exponent: f64) -> Vec<SlaterOrbital> {
+        ion_pos
+            .outer_iter()
+            .map(|center| {
+                SlaterOrbital::new(center.to_owned(), 1, exponent, AngularPart::spherical(0, 0))
+            })
+            .collect()
+    }
+
+    // values of both orbitals at the positions of both electrons
+    fn orbital_values(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        let mut values = Array2::zeros((2, 2));
+        for (i, x) in cfg.outer_iter().enumerate() {
+            for (j, phi) in self.orbitals.iter().enumerate() {
+                values[[i, j]] = phi.value(&x.to_owned())?;
+            }
+        }
+        Ok(values)
+    }
 }
 
 impl WaveFunction for HydrogenMoleculeWaveFunction {
@@ -89,14 +79,8 @@ impl Function<f64> for HydrogenMoleculeWaveFunction {
     type D = Ix2;
 
     fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
-        let x1 = cfg.slice(s![0, ..]);
-        let x2 = cfg.slice(s![1, ..]);
-        // parameters
-        let r = Array1::<f64>::from_vec(vec![self.nuclear_separation, 0.0, 0.0]);
-        Ok(
-            self.phi.value(&(&x1 - &(0.5 * &r))) * self.phi.value(&(&x2 + &(0.5 * &r)))
-                + self.phi.value(&(&x1 + &(0.5 * &r))) * self.phi.value(&(&x2 - &(0.5 * &r))),
-        )
+        let phi = self.orbital_values(cfg)?;
+        Ok(phi[[0, 0]] * phi[[1, 1]] + phi[[0, 1]] * phi[[1, 0]])
     }
 }
 
@@ -104,62 +88,52 @@ impl Differentiate for HydrogenMoleculeWaveFunction {
     type D = Ix2;
 
     fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
-        let x1 = cfg.slice(s![0, ..]);
-        let x2 = cfg.slice(s![1, ..]);
-        // parameters
-        let r = Array1::<f64>::from_vec(vec![self.nuclear_separation, 0.0, 0.0]);
-        //
+        let phi = self.orbital_values(cfg)?;
         let mut grad = Array2::<f64>::zeros((2, 3));
-        let mut gradx1 = grad.slice_mut(s![0, ..]);
-        gradx1 += &(self.phi.value(&(&x2 + &(0.5 * &r))) * self.phi.gradient(&(&x1 - &(0.5 * &r)))
-            + self.phi.value(&(&x2 - &(0.5 * &r))) * self.phi.gradient(&(&x1 + &(0.5 * &r))));
-
-        let mut gradx2 = grad.slice_mut(s![1, ..]);
-        gradx2 += &(self.phi.value(&(&x1 + &(0.5 * &r))) * self.phi.gradient(&(&x2 - &(0.5 * &r)))
-            + self.phi.value(&(&x1 - &(0.5 * &r))) * self.phi.gradient(&(&x2 + &(0.5 * &r))));
-
+        for i in 0..2 {
+            let x = cfg.row(i).to_owned();
+            // the other electron occupies the other orbital
+            let other = 1 - i;
+            let mut row = grad.row_mut(i);
+            row += &(self.orbitals[0].gradient(&x)? * phi[[other, 1]]);
+            row += &(self.orbitals[1].gradient(&x)? * phi[[other, 0]]);
+        }
         Ok(grad)
     }
 
     fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
-        let x1 = cfg.slice(s![0, ..]);
-        let x2 = cfg.slice(s![1, ..]);
-        // parameters
-        let r = Array1::<f64>::from_vec(vec![self.nuclear_separation, 0.0, 0.0]);
-        let laplpsi = &(self.phi.value(&(&x2 + &(0.5 * &r)))
-            * self.phi.laplacian(&(&x1 - &(0.5 * &r)))
-            + self.phi.value(&(&x2 - &(0.5 * &r))) * self.phi.laplacian(&(&x1 + &(0.5 * &r))))
-            + &(self.phi.value(&(&x1 + &(0.5 * &r))) * self.phi.laplacian(&(&x2 - &(0.5 * &r)))
-                + self.phi.value(&(&x1 - &(0.5 * &r))) * self.phi.laplacian(&(&x2 + &(0.5 * &r))));
-        Ok(laplpsi)
+        let phi = self.orbital_values(cfg)?;
+        let mut laplacian = 0.0;
+        for i in 0..2 {
+            let x = cfg.row(i).to_owned();
+            let other = 1 - i;
+            laplacian += self.orbitals[0].laplacian(&x)? * phi[[other, 1]]
+                + self.orbitals[1].laplacian(&x)? * phi[[other, 0]];
+        }
+        Ok(laplacian)
     }
 }
 
 impl Optimize for HydrogenMoleculeWaveFunction {
     fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
-        let x1 = cfg.slice(s![0, ..]);
-        let x2 = cfg.slice(s![1, ..]);
-        // parameters
-        let r = Array1::<f64>::from_vec(vec![self.nuclear_separation, 0.0, 0.0]);
+        let phi = self.orbital_values(cfg)?;
+        let (x1, x2) = (cfg.row(0).to_owned(), cfg.row(1).to_owned());
+        let (a, b) = (&self.orbitals[0], &self.orbitals[1]);
         Ok(array![
-            self.phi.value(&(&x1 - &(0.5 * &r)))
-                * self.phi.parameter_gradient(&(&x2 + &(0.5 * &r)))
-                + self.phi.parameter_gradient(&(&x1 - &(0.5 * &r)))
-                    * self.phi.value(&(&x2 + &(0.5 * &r)))
-                + self.phi.value(&(&x1 + &(0.5 * &r)))
-                    * self.phi.parameter_gradient(&(&x2 - &(0.5 * &r)))
-                + self.phi.parameter_gradient(&(&x1 + &(0.5 * &r)))
-                    * self.phi.value(&(&x2 - &(0.5 * &r)))
+            a.exponent_derivative(&x1)? * phi[[1, 1]]
+                + phi[[0, 0]] * b.exponent_derivative(&x2)?
+                + b.exponent_derivative(&x1)? * phi[[1, 0]]
+                + phi[[0, 1]] * a.exponent_derivative(&x2)?
         ])
     }
 
     fn update_parameters(&mut self, deltap: &Array1<f64>) {
         self.params += deltap;
-        self.phi.update_parameters(deltap[0]);
+        self.orbitals = Self::orbitals(&self.ion_pos, self.params[0]);
     }
 
-    fn parameters(&self) -> &Array1<f64> {
-        &self.params
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
     }
 
     fn num_parameters(&self) -> usize {
@@ -182,21 +156,20 @@ static BLOCK_SIZE: usize = 10;
 fn main() {
     // H2 equilibrium geometry
     let ion_pos = array![[-0.7, 0.0, 0.0], [0.7, 0.0, 0.0]];
-    let sep = ion_pos[[1, 0]] - ion_pos[[0, 0]];
 
     // construct  wave function
-    let wave_function = HydrogenMoleculeWaveFunction::new(sep, array![0.5]);
+    let wave_function = HydrogenMoleculeWaveFunction::new(ion_pos.clone(), array![0.5]);
 
     // run optimization for two different optimizers
     println!("STOCHASTIC RECONFIGURATION");
     let (sr_wf, energies_sr, errors_sr) = optimize_wave_function(
         &ion_pos,
         wave_function.clone(),
-        StochasticReconfiguration::new(50_000.0),
+        StochasticReconfiguration::new(1.0),
     );
     println!("\nSTEEPEST DESCENT");
     let (_sd_wf, energies_sd, errors_sd) =
-        optimize_wave_function(&ion_pos, wave_function.clone(), SteepestDescent::new(1e-5));
+        optimize_wave_function(&ion_pos, wave_function.clone(), SteepestDescent::new(0.1));
     println!();
 
     const NUM_WALKERS: usize = 100;
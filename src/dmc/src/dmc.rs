@@ -50,7 +50,10 @@ where
             (
                 1.0, 
                 Array2::random_using(
-                    (guiding_wave_function.num_electrons(), 3),
+                    (
+                        guiding_wave_function.num_electrons(),
+                        guiding_wave_function.dimension(),
+                    ),
                     Normal::new(0.0, 1.0),
                     &mut rng,
                 )
@@ -86,16 +89,16 @@ where
 
                 for (weight, conf) in self.walkers.iter_mut() {
                     // compute old local energy
-                    let wave_function_value_old = self.guiding_wave_function.value(&conf).unwrap();
-                    let local_e = self
+                    let log_vgl_old = self.guiding_wave_function.log_vgl(&conf).unwrap();
+                    let local_e = *self
                         .hamiltonian
-                        .act_on(&self.guiding_wave_function, &conf)
+                        .local_value(&self.guiding_wave_function, &conf, &log_vgl_old)
                         .unwrap()
                         .get_scalar()
-                        .unwrap()
-                        / wave_function_value_old;
+                        .unwrap();
                     // move all electrons according to Langevin dynamics
                     // with accept/reject
+                    self.guiding_wave_function.refresh(conf).unwrap();
                     for e in 0..self.guiding_wave_function.num_electrons() {
                         let new_conf = if let Some(x) = self
                             .metrop
@@ -112,16 +115,14 @@ where
                     ensemble_energy += *weight * local_e;
                     total_weight += *weight;
 
-                    let wave_function_value_new = self.guiding_wave_function.value(&conf).unwrap();
-
                     // compute local energy after move
-                    let local_e_new = self
+                    let log_vgl_new = self.guiding_wave_function.log_vgl(&conf).unwrap();
+                    let local_e_new = *self
                         .hamiltonian
-                        .act_on(&self.guiding_wave_function, conf)
+                        .local_value(&self.guiding_wave_function, conf, &log_vgl_new)
                         .unwrap()
                         .get_scalar()
-                        .unwrap()
-                        / wave_function_value_new;
+                        .unwrap();
                     // update weight of this walker
                     *weight *= f64::exp(
                         -time_step * ((local_e + local_e_new)/2.0 - self.reference_energy),
@@ -1,5 +1,6 @@
 // Standard imports
 use std::convert;
+use std::io;
 // Third party imports
 use ndarray::ShapeError;
 use ndarray_linalg::error::LinalgError;
@@ -10,8 +11,14 @@ pub enum Error {
     ShapeError(ShapeError),
     FuncError,
     OperatorValueAccessError,
+    OperatorValueMismatch,
     DataAccessError,
+    ParameterAccessError(String),
     EmptyCacheError,
+    ParseError(String),
+    InputError(String),
+    IoError(io::Error),
+    SerializationError(String),
 }
 
 impl convert::From<LinalgError> for Error {
@@ -25,3 +32,9 @@ impl convert::From<ShapeError> for Error {
         Error::ShapeError(e)
     }
 }
+
+impl convert::From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::IoError(e)
+    }
+}
@@ -5,6 +5,7 @@ pub use montecarlo;
 pub use operator;
 pub use optimize;
 pub use vmc;
+pub use wavefunction;
 pub use wavefunction_traits;
 
 pub mod prelude {
@@ -17,5 +18,6 @@ pub mod prelude {
     pub use optimize::*;
     pub use util::*;
     pub use vmc::*;
+    pub use wavefunction::*;
     pub use wavefunction_traits::*;
 }
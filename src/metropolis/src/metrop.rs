@@ -1,4 +1,4 @@
-use ndarray::{Array, Array1, Array2, Ix2};
+use ndarray::{Array, Array1, Array2, Axis, Ix2};
 use ndarray_linalg::Norm;
 use ndarray_rand::RandomExt;
 use rand::distributions::{Normal, Range};
@@ -7,23 +7,22 @@ use rand::{FromEntropy, Rng, SeedableRng};
 
 use crate::traits::Metropolis;
 use errors::Error;
-use wavefunction_traits::{Differentiate, Function};
+use wavefunction_traits::{Differentiate, Field, Function, SimulationCell, WaveFunction};
 
 type Result<T> = std::result::Result<T, Error>;
 
-#[allow(dead_code)]
-type Vgl = (f64, Array2<f64>, f64);
-
 /// Simplest Metropolis algorithm.
 /// Transition matrix T(x -> x') is constant inside a cubical box,
 /// and zero outside it. This yields an acceptance probability of
-/// $A(x -> x') = \min(\psi(x')^2 / \psi(x)^2, 1)$.
+/// $A(x -> x') = \min(|\psi(x')|^2 / |\psi(x)|^2, 1)$, so that real and
+/// complex wave functions alike are sampled from $|\psi|^2$.
 #[derive(Clone)]
 pub struct MetropolisBox<R>
 where
     R: Rng,
 {
     box_side: f64,
+    cell: Option<SimulationCell>,
     rng: R,
 }
 
@@ -32,7 +31,17 @@ where
     R: Rng,
 {
     pub fn from_rng(box_side: f64, rng: R) -> Self {
-        Self { box_side, rng }
+        Self {
+            box_side,
+            cell: None,
+            rng,
+        }
+    }
+
+    /// Wrap proposed positions back into the periodic cell `cell`.
+    pub fn with_cell(mut self, cell: SimulationCell) -> Self {
+        self.cell = Some(cell);
+        self
     }
 }
 
@@ -40,14 +49,16 @@ impl MetropolisBox<StdRng> {
     pub fn new(box_side: f64) -> Self {
         Self {
             box_side,
+            cell: None,
             rng: StdRng::from_entropy(),
         }
     }
 }
 
-impl<T, R> Metropolis<T> for MetropolisBox<R>
+impl<T, F, R> Metropolis<T, F> for MetropolisBox<R>
 where
-    T: Function<f64, D = Ix2> + Clone,
+    T: Function<F, D = Ix2> + WaveFunction<F> + Clone,
+    F: Field,
     R: Rng + SeedableRng,
     <R as SeedableRng>::Seed: From<[u8; 32]>,
 {
@@ -62,11 +73,12 @@ where
         {
             let mut mov_slice = config_proposed.slice_mut(s![idx, ..]);
             mov_slice += &Array1::random_using(
-                3,
+                cfg.len_of(Axis(1)),
                 Range::new(-0.5 * self.box_side, 0.5 * self.box_side),
                 &mut self.rng,
             );
         }
+        wrap_electron(&self.cell, &mut config_proposed, idx);
         Ok(config_proposed)
     }
 
@@ -75,9 +87,10 @@ where
         wf: &mut T,
         cfg: &Array2<f64>,
         cfg_prop: &Array2<f64>,
+        idx: usize,
     ) -> Result<bool> {
-        let wf_value = wf.value(cfg_prop)?;
-        let acceptance = (wf_value.powi(2) / wf.value(cfg)?.powi(2)).min(1.0);
+        let ratio = wf.ratio(cfg, &cfg_prop.row(idx).to_owned(), idx)?;
+        let acceptance = ratio.abs_sqr().min(1.0);
         Ok(acceptance > self.rng.gen::<f64>())
     }
 
@@ -88,9 +101,11 @@ where
         idx: usize,
     ) -> Result<Option<Array2<f64>>> {
         let cfg_proposed = self.propose_move(wf, cfg, idx)?;
-        if self.accept_move(wf, cfg, &cfg_proposed)? {
+        if self.accept_move(wf, cfg, &cfg_proposed, idx)? {
+            wf.accept_move(cfg, &cfg_proposed.row(idx).to_owned(), idx)?;
             Ok(Some(cfg_proposed))
         } else {
+            wf.reject_move(idx);
             Ok(None)
         }
     }
@@ -107,6 +122,7 @@ where
 {
     time_step: f64,
     fixed_node: bool,
+    cell: Option<SimulationCell>,
     rng: R,
 }
 
@@ -115,6 +131,7 @@ impl<R: Rng> MetropolisDiffuse<R> {
         Self {
             time_step,
             fixed_node: false,
+            cell: None,
             rng,
         }
     }
@@ -123,6 +140,13 @@ impl<R: Rng> MetropolisDiffuse<R> {
         self.fixed_node = true;
         self
     }
+
+    /// Wrap proposed positions back into the periodic cell `cell`; the
+    /// Green's functions then use the minimum image of each displacement.
+    pub fn with_cell(mut self, cell: SimulationCell) -> Self {
+        self.cell = Some(cell);
+        self
+    }
 }
 
 impl MetropolisDiffuse<StdRng> {
@@ -130,14 +154,16 @@ impl MetropolisDiffuse<StdRng> {
         Self {
             time_step,
             fixed_node: false,
+            cell: None,
             rng: StdRng::from_entropy(),
         }
     }
 }
 
-impl<T, R> Metropolis<T> for MetropolisDiffuse<R>
+impl<T, F, R> Metropolis<T, F> for MetropolisDiffuse<R>
 where
-    T: Differentiate<D = Ix2> + Function<f64, D = Ix2> + Clone,
+    T: Differentiate<F, D = Ix2> + Function<F, D = Ix2> + WaveFunction<F> + Clone,
+    F: Field,
     R: Rng + SeedableRng,
     <R as SeedableRng>::Seed: From<[u8; 32]>,
 {
@@ -150,15 +176,17 @@ where
     fn propose_move(&mut self, wf: &mut T, cfg: &Array2<f64>, idx: usize) -> Result<Array2<f64>> {
         let mut config_proposed = cfg.clone();
         {
-            let wf_value = wf.value(cfg)?;
-            let wf_grad = wf.gradient(cfg)?;
-            let drift_velocity = &wf_grad.slice(s![idx, ..]) / wf_value;
+            let drift_velocity = drift(wf, cfg, &cfg.row(idx).to_owned(), idx)?;
 
             let mut mov_slice = config_proposed.slice_mut(s![idx, ..]);
             mov_slice += &(drift_velocity * self.time_step);
-            mov_slice +=
-                &Array1::random_using(3, Normal::new(0.0, self.time_step.sqrt()), &mut self.rng);
+            mov_slice += &Array1::random_using(
+                cfg.len_of(Axis(1)),
+                Normal::new(0.0, self.time_step.sqrt()),
+                &mut self.rng,
+            );
         }
+        wrap_electron(&self.cell, &mut config_proposed, idx);
         Ok(config_proposed)
     }
 
@@ -167,32 +195,40 @@ where
         wf: &mut T,
         cfg: &Array2<f64>,
         cfg_prop: &Array2<f64>,
+        idx: usize,
     ) -> Result<bool> {
-        let wf_value = wf.value(cfg_prop)?;
-        let wf_grad = wf.gradient(cfg_prop)?;
-        let drift_velocity = &wf_grad / wf_value;
-        let wf_value_old = wf.value(cfg)?;
-        let wf_grad_old = wf.gradient(cfg)?;
-        let drift_velocity_old = &wf_grad_old / wf_value_old;
-
-        if wf_value.signum() != wf_value_old.signum() {
+        let pos_old = cfg.row(idx).to_owned();
+        let pos_new = cfg_prop.row(idx).to_owned();
+        let ratio = wf.ratio(cfg, &pos_new, idx)?;
+
+        // a real wave function changes sign when the move crosses a node
+        if ratio.imag() == 0.0 && ratio.real() < 0.0 {
             return Ok(false);
         }
 
+        let drift_velocity = drift(wf, cfg, &pos_new, idx)?;
+        let drift_velocity_old = drift(wf, cfg, &pos_old, idx)?;
+        let displacement = match &self.cell {
+            Some(cell) => cell.minimum_image(&(&pos_new - &pos_old)),
+            None => &pos_new - &pos_old,
+        };
+
+        // only electron `idx` moved, so the Green's functions
+        // reduce to those of a single electron
         let t_high = f64::exp(
-            -(&(cfg - cfg_prop) - &(&drift_velocity * self.time_step))
+            -(&displacement + &(&drift_velocity * self.time_step))
                 .norm_l2()
                 .powi(2)
                 / (2.0 * self.time_step),
         );
         let t_low = f64::exp(
-            -(&(cfg_prop - cfg) - &(&drift_velocity_old * self.time_step))
+            -(&displacement - &(&drift_velocity_old * self.time_step))
                 .norm_l2()
                 .powi(2)
                 / (2.0 * self.time_step),
         );
 
-        let acceptance = (t_high * wf_value.powi(2) / (t_low * wf_value_old.powi(2))).min(1.0);
+        let acceptance = (t_high * ratio.abs_sqr() / t_low).min(1.0);
 
         Ok(acceptance > self.rng.gen::<f64>())
     }
@@ -204,9 +240,11 @@ where
         idx: usize,
     ) -> Result<Option<Array2<f64>>> {
         let cfg_proposed = self.propose_move(wf, cfg, idx)?;
-        if self.accept_move(wf, cfg, &cfg_proposed)? {
+        if self.accept_move(wf, cfg, &cfg_proposed, idx)? {
+            wf.accept_move(cfg, &cfg_proposed.row(idx).to_owned(), idx)?;
             Ok(Some(cfg_proposed))
         } else {
+            wf.reject_move(idx);
             Ok(None)
         }
     }
@@ -216,9 +254,28 @@ where
     }
 }
 
+/// Drift velocity $\nabla_i \ln|\psi|$ of electron `idx` at `pos`, which is the
+/// real part of $\nabla_i \psi / \psi$.
+fn drift<T, F>(wf: &T, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<Array1<f64>>
+where
+    T: Differentiate<F, D = Ix2> + Function<F, D = Ix2> + WaveFunction<F>,
+    F: Field,
+{
+    Ok(wf.gradient_ratio(cfg, pos, idx)?.mapv(|g| g.real()))
+}
+
+/// Wrap electron `idx` back into `cell`, if the system is periodic.
+fn wrap_electron(cell: &Option<SimulationCell>, cfg: &mut Array2<f64>, idx: usize) {
+    if let Some(cell) = cell {
+        let wrapped = cell.wrap(&cfg.row(idx).to_owned());
+        cfg.row_mut(idx).assign(&wrapped);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ndarray_linalg::c64;
     use proptest::{collection::vec, num, prelude::*};
 
     // define stub wave function
@@ -242,6 +299,12 @@ mod tests {
         }
     }
 
+    impl WaveFunction for WaveFunctionMock {
+        fn num_electrons(&self) -> usize {
+            1
+        }
+    }
+
     impl Differentiate for WaveFunctionMock {
         type D = Ix2;
 
@@ -254,6 +317,26 @@ mod tests {
         }
     }
 
+    // plane wave $e^{ikx}$, of constant modulus
+    #[derive(Clone)]
+    struct PlaneWave {
+        k: f64,
+    }
+
+    impl Function<c64> for PlaneWave {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<c64> {
+            Ok(c64::new(0.0, self.k * cfg[[0, 0]]).exp())
+        }
+    }
+
+    impl WaveFunction<c64> for PlaneWave {
+        fn num_electrons(&self) -> usize {
+            1
+        }
+    }
+
     type Ovgl = (Option<f64>, Option<Array2<f64>>, Option<f64>);
 
     proptest! {
@@ -264,7 +347,37 @@ mod tests {
             let mut wf = WaveFunctionMock { value: 1.0 };
             let mut metrop = MetropolisBox::<StdRng>::new(1.0);
             let new_cfg = metrop.propose_move(&mut wf, &cfg, 0).unwrap(); // should always accept
-            assert!(metrop.accept_move(&mut wf, &cfg, &new_cfg).unwrap());
+            assert!(metrop.accept_move(&mut wf, &cfg, &new_cfg, 0).unwrap());
+        }
+
+        #[test]
+        fn test_complex_wf_samples_modulus(v in vec(num::f64::NORMAL, 3)) {
+            let cfg = Array2::<f64>::from_shape_vec((1, 3), v).unwrap();
+            let mut wf = PlaneWave { k: 2.0 };
+            let mut metrop = MetropolisBox::<StdRng>::new(1.0);
+            let new_cfg = metrop.propose_move(&mut wf, &cfg, 0).unwrap(); // |psi| is constant
+            assert!(metrop.accept_move(&mut wf, &cfg, &new_cfg, 0).unwrap());
+        }
+
+        #[test]
+        fn test_moves_respect_dimension(dim in 1_usize..4) {
+            let cfg = Array2::<f64>::zeros((2, dim));
+            let mut wf = WaveFunctionMock { value: 1.0 };
+            let mut metrop = MetropolisBox::<StdRng>::new(1.0);
+            let new_cfg = metrop.propose_move(&mut wf, &cfg, 1).unwrap();
+            assert_eq!(new_cfg.dim(), (2, dim));
+            assert_eq!(new_cfg.row(0), cfg.row(0));
+        }
+
+        #[test]
+        fn test_moves_wrap_into_cell(v in vec(-10.0..10.0, 3)) {
+            let cfg = Array2::<f64>::from_shape_vec((1, 3), v).unwrap();
+            let mut wf = WaveFunctionMock { value: 1.0 };
+            let cell = SimulationCell::cubic(2.0).unwrap();
+            let mut metrop = MetropolisBox::<StdRng>::new(5.0).with_cell(cell.clone());
+            let new_cfg = metrop.propose_move(&mut wf, &cfg, 0).unwrap();
+            let fractional = cell.fractional(&new_cfg.row(0).to_owned());
+            assert!(fractional.iter().all(|f| (0.0..1.0).contains(f)));
         }
     }
 }
@@ -1,26 +1,32 @@
 use errors::Error;
 use ndarray::{Array2, Ix2};
 use rand::Rng;
-use wavefunction_traits::{Differentiate, Function};
+use wavefunction_traits::{Function, WaveFunction};
 
 type Result<T> = std::result::Result<T, Error>;
 
 /// Interface for implementing Metropolis algorithms that generate
-/// Markov chains of configurations.
-pub trait Metropolis<T: Function<f64, D = Ix2> + Clone> {
+/// Markov chains of configurations, for wave functions taking values in `F`.
+pub trait Metropolis<T, F = f64>
+where
+    T: Function<F, D = Ix2> + WaveFunction<F> + Clone,
+{
     /// Rng type to use
     type R: Rng;
     /// Propose a move to a new configuration.
     fn propose_move(&mut self, wf: &mut T, cfg: &Array2<f64>, idx: usize) -> Result<Array2<f64>>;
-    /// Test whether a proposed configuration will be accepted.
+    /// Test whether a proposed configuration, in which electron `idx` was moved,
+    /// will be accepted.
     fn accept_move(
         &mut self,
         wf: &mut T,
         cfg: &Array2<f64>,
         cfg_prop: &Array2<f64>,
+        idx: usize,
     ) -> Result<bool>;
     /// Return an Option containing the new configuration if it was accepted,
-    /// and None otherwise.
+    /// and None otherwise. The wave function is notified of the outcome
+    /// through `WaveFunction::accept_move` or `WaveFunction::reject_move`.
     fn move_state(
         &mut self,
         wf: &mut T,
@@ -9,41 +9,39 @@ use rand::Rng;
 use crate::traits::*;
 use errors::Error;
 use metropolis::Metropolis;
-use operator::{
-    LocalOperator,
-    OperatorValue::{self, *},
-};
-use wavefunction_traits::{Function, WaveFunction};
+use operator::{LocalOperator, OperatorValue};
+use wavefunction_traits::{Differentiate, Field, Function, WaveFunction};
 
 /// Simple Monte Carlo sampler
-/// Performs Metropolis step and keeps list of observables to sample
+/// Performs Metropolis step and keeps list of observables to sample.
+/// Complex wave functions, with `F = c64`, are sampled from $|\psi|^2$.
 #[derive(Clone)]
-pub struct Sampler<'a, T, V>
+pub struct Sampler<'a, T, V, F = f64>
 where
-    T: Function<f64, D = Ix2> + Clone,
-    V: Metropolis<T>,
+    T: Function<F, D = Ix2> + WaveFunction<F> + Clone,
+    V: Metropolis<T, F>,
 {
     wave_function: T,
     config: Array2<f64>,
     metropolis: V,
-    observables: &'a HashMap<String, Box<dyn LocalOperator<T>>>,
+    observables: &'a HashMap<String, Box<dyn LocalOperator<T, F>>>,
     samples: HashMap<String, Vec<OperatorValue>>,
     acceptance: f64,
 }
 
-impl<'a, T, V> Sampler<'a, T, V>
+impl<'a, T, V, F> Sampler<'a, T, V, F>
 where
-    T: Function<f64, D = Ix2> + WaveFunction + Clone,
-    V: Metropolis<T>,
-    <V as Metropolis<T>>::R: Rng,
+    T: Function<F, D = Ix2> + WaveFunction<F> + Clone,
+    V: Metropolis<T, F>,
+    <V as Metropolis<T, F>>::R: Rng,
 {
     pub fn new(
         wave_function: T,
         mut metrop: V,
-        observables: &'a HashMap<String, Box<dyn LocalOperator<T>>>,
+        observables: &'a HashMap<String, Box<dyn LocalOperator<T, F>>>,
     ) -> Result<Self, Error> {
-        let nelec = wave_function.num_electrons();
-        let cfg = Array2::<f64>::random_using((nelec, 3), Range::new(-1., 1.), metrop.rng_mut());
+        let shape = (wave_function.num_electrons(), wave_function.dimension());
+        let cfg = Array2::<f64>::random_using(shape, Range::new(-1., 1.), metrop.rng_mut());
         Ok(Self {
             wave_function,
             config: cfg,
@@ -57,7 +55,7 @@ where
     pub fn with_initial_configuration(
         wave_function: T,
         metrop: V,
-        observables: &'a HashMap<String, Box<dyn LocalOperator<T>>>,
+        observables: &'a HashMap<String, Box<dyn LocalOperator<T, F>>>,
         cfg: Array2<f64>,
     ) -> Result<Self, Error> {
         Ok(Self {
@@ -71,23 +69,25 @@ where
     }
 }
 
-impl<'a, T, V> MonteCarloSampler for Sampler<'a, T, V>
+impl<'a, T, V, F> MonteCarloSampler for Sampler<'a, T, V, F>
 where
-    T: Function<f64, D = Ix2> + WaveFunction + Clone,
-    V: Metropolis<T>,
+    T: Function<F, D = Ix2> + Differentiate<F, D = Ix2> + WaveFunction<F> + Clone,
+    V: Metropolis<T, F>,
+    F: Field + Into<OperatorValue>,
 {
     type WaveFunc = T;
 
     fn sample(&mut self) -> Result<(), Error> {
-        // First sample all observables on the current configuration
+        // First sample all observables on the current configuration,
+        // evaluating the wave function only once
+        let log_vgl = self.wave_function.log_vgl(&self.config)?;
         let samples: Result<Vec<(String, OperatorValue)>, Error> = self
             .observables
             .iter()
             .map(|(name, operator)| {
                 Ok((
                     name.clone(),
-                    operator.act_on(&self.wave_function, &self.config)?
-                        / Scalar(self.wave_function().value(&self.config)?),
+                    operator.local_value(&self.wave_function, &self.config, &log_vgl)?,
                 ))
             })
             .collect();
@@ -104,6 +104,9 @@ where
     }
 
     fn move_state(&mut self) -> Result<(), Error> {
+        // recompute cached wave function state once per sweep, which also
+        // bounds the accumulation of round-off errors in incremental updates
+        self.wave_function.refresh(&self.config)?;
         for e in 0..self.wave_function.num_electrons() {
             if let Some(config) =
                 self.metropolis
@@ -0,0 +1,230 @@
+// Standard imports
+use std::ops::{Add, Mul};
+// Third party imports
+use ndarray::{Array2, Ix2};
+// First party imports
+use crate::traits::{LocalOperator, OperatorValue};
+use errors::Error;
+use wavefunction_traits::{Field, LogVgl};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Sum $\hat{A} + \hat{B}$ of two operators, e.g. a kinetic energy and a
+/// potential. Longer sums are built by nesting, or by adding boxed operators
+/// with `+`.
+#[derive(Clone)]
+pub struct OperatorSum<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> OperatorSum<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<T, F, A, B> LocalOperator<T, F> for OperatorSum<A, B>
+where
+    A: LocalOperator<T, F>,
+    B: LocalOperator<T, F>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        self.a.act_on(wf, cfg)? + self.b.act_on(wf, cfg)?
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue>
+    where
+        F: Field + Into<OperatorValue>,
+    {
+        self.a.local_value(wf, cfg, log_vgl)? + self.b.local_value(wf, cfg, log_vgl)?
+    }
+}
+
+/// Operator $c\hat{A}$, scaled by a real constant $c$.
+#[derive(Clone)]
+pub struct ScaledOperator<A> {
+    scale: f64,
+    operator: A,
+}
+
+impl<A> ScaledOperator<A> {
+    pub fn new(scale: f64, operator: A) -> Self {
+        Self { scale, operator }
+    }
+}
+
+impl<T, F, A> LocalOperator<T, F> for ScaledOperator<A>
+where
+    A: LocalOperator<T, F>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        Ok(self.operator.act_on(wf, cfg)?.scale(self.scale))
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue>
+    where
+        F: Field + Into<OperatorValue>,
+    {
+        Ok(self
+            .operator
+            .local_value(wf, cfg, log_vgl)?
+            .scale(self.scale))
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for Box<dyn LocalOperator<T, F>> {
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        (**self).act_on(wf, cfg)
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue>
+    where
+        F: Field + Into<OperatorValue>,
+    {
+        (**self).local_value(wf, cfg, log_vgl)
+    }
+}
+
+/// Boxed operators can be summed with any operator, e.g.
+/// `Box::new(KineticEnergy::new()) as Box<dyn LocalOperator<T>> + IonicPotential::new(..)`.
+impl<T, F, B> Add<B> for Box<dyn LocalOperator<T, F>>
+where
+    T: 'static,
+    F: 'static,
+    B: LocalOperator<T, F> + 'static,
+{
+    type Output = Box<dyn LocalOperator<T, F>>;
+
+    fn add(self, other: B) -> Self::Output {
+        Box::new(OperatorSum::new(self, other))
+    }
+}
+
+impl<T: 'static, F: 'static> Mul<f64> for Box<dyn LocalOperator<T, F>> {
+    type Output = Box<dyn LocalOperator<T, F>>;
+
+    fn mul(self, scale: f64) -> Self::Output {
+        Box::new(ScaledOperator::new(scale, self))
+    }
+}
+
+impl<T: 'static, F: 'static> Mul<Box<dyn LocalOperator<T, F>>> for f64 {
+    type Output = Box<dyn LocalOperator<T, F>>;
+
+    fn mul(self, operator: Box<dyn LocalOperator<T, F>>) -> Self::Output {
+        Box::new(ScaledOperator::new(self, operator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operator::{
+        ElectronicHamiltonian, ElectronicPotential, IonicPotential, KineticEnergy,
+    };
+    use ndarray::{array, Array1, Axis};
+    use wavefunction_traits::{Differentiate, Function};
+
+    // hydrogen-like 1s orbitals of two electrons, without normalization
+    struct TwoElectrons;
+
+    impl Function<f64> for TwoElectrons {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+            Ok(cfg
+                .outer_iter()
+                .map(|r| -r.dot(&r).sqrt())
+                .sum::<f64>()
+                .exp())
+        }
+    }
+
+    impl Differentiate for TwoElectrons {
+        type D = Ix2;
+
+        fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+            let mut grad = cfg.clone();
+            for mut r in grad.outer_iter_mut() {
+                let norm = r.dot(&r).sqrt();
+                r /= -norm;
+            }
+            Ok(grad * self.value(cfg)?)
+        }
+
+        fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+            let inverse_distances: f64 = cfg.outer_iter().map(|r| 1.0 / r.dot(&r).sqrt()).sum();
+            Ok(self.value(cfg)? * (cfg.len_of(Axis(0)) as f64 - 2.0 * inverse_distances))
+        }
+    }
+
+    #[test]
+    fn sum_matches_hamiltonian() {
+        let ions = array![[0.0, 0.0, 0.0], [0.0, 0.0, 1.4]];
+        let charges: Array1<i32> = array![1, 1];
+        let cfg = array![[0.3, -0.2, 0.5], [-0.4, 0.1, 1.1]];
+        let wf = TwoElectrons;
+        let log_vgl = wf.log_vgl(&cfg).unwrap();
+
+        let h = ElectronicHamiltonian::from_ions(ions.clone(), charges.clone());
+        let expected = h.local_value(&wf, &cfg, &log_vgl).unwrap();
+
+        let sum = OperatorSum::new(
+            OperatorSum::new(
+                KineticEnergy::new(),
+                IonicPotential::new(ions.clone(), charges.clone()),
+            ),
+            ElectronicPotential::new(),
+        );
+        assert_eq!(sum.local_value(&wf, &cfg, &log_vgl).unwrap(), expected);
+
+        let boxed = Box::new(KineticEnergy::new()) as Box<dyn LocalOperator<TwoElectrons>>
+            + IonicPotential::new(ions, charges)
+            + ElectronicPotential::new();
+        let value = *boxed
+            .local_value(&wf, &cfg, &log_vgl)
+            .unwrap()
+            .get_scalar()
+            .unwrap();
+        assert!((value - expected.get_scalar().unwrap()).abs() < 1e-12);
+        let direct = *(boxed.act_on(&wf, &cfg).unwrap()
+            / OperatorValue::Scalar(wf.value(&cfg).unwrap()))
+        .unwrap()
+        .get_scalar()
+        .unwrap();
+        assert!((direct - value).abs() < 1e-12);
+    }
+
+    #[test]
+    fn scaling() {
+        let cfg = array![[0.3, -0.2, 0.5], [-0.4, 0.1, 1.1]];
+        let wf = TwoElectrons;
+        let log_vgl = wf.log_vgl(&cfg).unwrap();
+        let v = ElectronicPotential::new().value(&cfg).unwrap();
+
+        let scaled = ScaledOperator::new(-2.5, ElectronicPotential::new());
+        let value = scaled.local_value(&wf, &cfg, &log_vgl).unwrap();
+        assert_eq!(*value.get_scalar().unwrap(), -2.5 * v);
+
+        let boxed = Box::new(ElectronicPotential::new()) as Box<dyn LocalOperator<TwoElectrons>>;
+        let boxed = 3.0 * (boxed * 0.5);
+        let value = boxed.local_value(&wf, &cfg, &log_vgl).unwrap();
+        assert!((value.get_scalar().unwrap() - 1.5 * v).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,330 @@
+// Standard imports
+use std::f64::consts::PI;
+// Third party imports
+use libm::erfc;
+use ndarray::{Array1, Array2, Axis, Ix2};
+use ndarray_linalg::Norm;
+// First party imports
+use crate::traits::{
+    LocalOperator,
+    OperatorValue::{self, *},
+};
+use errors::Error;
+use wavefunction_traits::{check_input, Field, Function, LogVgl, SimulationCell};
+
+/// Ewald summation of the Coulomb energy of point charges in a periodic
+/// three-dimensional `SimulationCell`. The $1/r$ interaction is split into a
+/// short-ranged part $\mathrm{erfc}(\alpha r)/r$, summed over lattice images,
+/// and a smooth part summed over reciprocal lattice vectors. Any net charge is
+/// compensated by a uniform background, as in the homogeneous electron gas.
+#[derive(Clone)]
+pub struct Ewald {
+    cell: SimulationCell,
+    alpha: f64,
+    real_cutoff: f64,
+    // lattice vectors in the rows, with their squared lengths
+    lattice_points: Array2<f64>,
+    lattice_norms: Array1<f64>,
+    // reciprocal lattice vectors in the rows, with weights
+    // $2\pi e^{-G^2/4\alpha^2} / (V G^2)$
+    reciprocal_points: Array2<f64>,
+    reciprocal_weights: Array1<f64>,
+    // interaction of a unit charge with its own images
+    self_energy: f64,
+}
+
+impl Ewald {
+    // both sums converge as $e^{-x^2}$ in the cutoffs $x = \alpha r_c = G_c / 2\alpha$
+    const CUTOFF: f64 = 5.3;
+
+    pub fn new(cell: SimulationCell) -> Result<Self, Error> {
+        check_input(
+            cell.dimension() == 3,
+            "Ewald sums require a three-dimensional cell",
+        )?;
+        // balances the number of terms in both sums, independent of system size
+        let alpha = PI.sqrt() / cell.volume().cbrt();
+        let real_cutoff = Self::CUTOFF / alpha;
+        // separations are minimum images, so their length is bounded by
+        // half the sum of the lattice vector lengths
+        let image_extent = 0.5
+            * cell
+                .lattice()
+                .outer_iter()
+                .map(|a| a.norm_l2())
+                .sum::<f64>();
+        let lattice_points = cell.lattice_points(real_cutoff + image_extent);
+        let images: f64 = lattice_points
+            .iter()
+            .skip(1)
+            .map(|l| l.norm_l2())
+            .filter(|&l| l < real_cutoff)
+            .map(|l| erfc(alpha * l) / l)
+            .sum();
+        let lattice_points = to_rows(&lattice_points);
+        let lattice_norms = lattice_points.map_axis(Axis(1), |l| l.dot(&l));
+        let reciprocal_points = to_rows(
+            &cell
+                .reciprocal_points(2.0 * alpha * Self::CUTOFF)
+                .split_off(1),
+        );
+        let reciprocal_weights = reciprocal_points.map_axis(Axis(1), |g| {
+            let g2 = g.dot(&g);
+            2.0 * PI / cell.volume() * (-g2 / (4.0 * alpha.powi(2))).exp() / g2
+        });
+        Ok(Self {
+            self_energy: 0.5 * images - alpha / PI.sqrt(),
+            cell,
+            alpha,
+            real_cutoff,
+            lattice_points,
+            lattice_norms,
+            reciprocal_points,
+            reciprocal_weights,
+        })
+    }
+
+    pub fn cell(&self) -> &SimulationCell {
+        &self.cell
+    }
+
+    /// Energy of charges `charges` at the rows of `positions`, including the
+    /// interaction of each charge with its own periodic images, and with the
+    /// neutralizing background.
+    pub fn energy(&self, positions: &Array2<f64>, charges: &Array1<f64>) -> f64 {
+        let num_charges = positions.len_of(Axis(0));
+        let mut energy = 0.0;
+        for i in 0..num_charges {
+            for j in i + 1..num_charges {
+                let separation = &positions.row(i) - &positions.row(j);
+                energy += charges[i] * charges[j] * self.real_space(&separation);
+            }
+        }
+        let (re, im) = self.structure_factors(positions, charges);
+        energy += self.reciprocal_weights.dot(&(&re * &re + &im * &im));
+        let total_charge = charges.sum();
+        energy + self.self_energy * charges.dot(charges)
+            - PI / (2.0 * self.cell.volume() * self.alpha.powi(2)) * total_charge.powi(2)
+    }
+
+    /// Interaction energy between two sets of charges, such that the energy of
+    /// the combined set is `energy(a) + energy(b) + interaction(a, b)`.
+    pub fn interaction(
+        &self,
+        positions_a: &Array2<f64>,
+        charges_a: &Array1<f64>,
+        positions_b: &Array2<f64>,
+        charges_b: &Array1<f64>,
+    ) -> f64 {
+        let mut energy = 0.0;
+        for (pos_a, q_a) in positions_a.outer_iter().zip(charges_a.iter()) {
+            for (pos_b, q_b) in positions_b.outer_iter().zip(charges_b.iter()) {
+                energy += q_a * q_b * self.real_space(&(&pos_a - &pos_b));
+            }
+        }
+        let (re_a, im_a) = self.structure_factors(positions_a, charges_a);
+        let (re_b, im_b) = self.structure_factors(positions_b, charges_b);
+        energy += 2.0
+            * self
+                .reciprocal_weights
+                .dot(&(&re_a * &re_b + &im_a * &im_b));
+        energy - PI / (self.cell.volume() * self.alpha.powi(2)) * charges_a.sum() * charges_b.sum()
+    }
+
+    /// Short-ranged interaction $\sum_{\mathbf{L}} \mathrm{erfc}(\alpha
+    /// |\mathbf{r} + \mathbf{L}|) / |\mathbf{r} + \mathbf{L}|$ of two unit charges.
+    fn real_space(&self, separation: &Array1<f64>) -> f64 {
+        let image = self.cell.minimum_image(separation);
+        let image_norm = image.dot(&image);
+        self.lattice_points
+            .dot(&image)
+            .iter()
+            .zip(self.lattice_norms.iter())
+            .map(|(overlap, norm)| (norm + 2.0 * overlap + image_norm).sqrt())
+            .filter(|&r| r < self.real_cutoff)
+            .map(|r| erfc(self.alpha * r) / r)
+            .sum()
+    }
+
+    /// Real and imaginary parts of the structure factors
+    /// $S(\mathbf{G}) = \sum_i q_i e^{i\mathbf{G}\cdot\mathbf{r}_i}$.
+    fn structure_factors(
+        &self,
+        positions: &Array2<f64>,
+        charges: &Array1<f64>,
+    ) -> (Array1<f64>, Array1<f64>) {
+        let phases = positions.dot(&self.reciprocal_points.t());
+        (
+            charges.dot(&phases.mapv(f64::cos)),
+            charges.dot(&phases.mapv(f64::sin)),
+        )
+    }
+}
+
+/// Collect vectors into the rows of a matrix.
+fn to_rows(points: &[Array1<f64>]) -> Array2<f64> {
+    let dim = points[0].len();
+    Array2::from_shape_fn((points.len(), dim), |(i, k)| points[i][k])
+}
+
+/// Electron-electron interaction in a periodic cell, including the interaction
+/// with a uniform positive background, e.g. for the homogeneous electron gas.
+/// In a solid, the background cancels against that of `PeriodicIonicPotential`.
+#[derive(Clone)]
+pub struct PeriodicElectronicPotential {
+    ewald: Ewald,
+}
+
+impl PeriodicElectronicPotential {
+    pub fn new(cell: SimulationCell) -> Result<Self, Error> {
+        Ok(Self {
+            ewald: Ewald::new(cell)?,
+        })
+    }
+
+    pub fn ewald(&self) -> &Ewald {
+        &self.ewald
+    }
+}
+
+impl Function<f64> for PeriodicElectronicPotential {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64, Error> {
+        let charges = Array1::from_elem(cfg.len_of(Axis(0)), -1.0);
+        Ok(self.ewald.energy(cfg, &charges))
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for PeriodicElectronicPotential
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
+    }
+}
+
+/// Electron-ion and ion-ion interaction of a crystal, for ions of charge
+/// `ion_charge` at `ion_positions` in a periodic cell. Together with
+/// `PeriodicElectronicPotential` this is the Ewald energy of the neutral cell.
+#[derive(Clone)]
+pub struct PeriodicIonicPotential {
+    ewald: Ewald,
+    ion_positions: Array2<f64>,
+    ion_charge: Array1<f64>,
+    ionic_repulsion: f64,
+}
+
+impl PeriodicIonicPotential {
+    pub fn new(
+        cell: SimulationCell,
+        ion_positions: Array2<f64>,
+        ion_charge: Array1<i32>,
+    ) -> Result<Self, Error> {
+        let ewald = Ewald::new(cell)?;
+        let ion_charge = ion_charge.mapv(f64::from);
+        let ionic_repulsion = ewald.energy(&ion_positions, &ion_charge);
+        Ok(Self {
+            ewald,
+            ion_positions,
+            ion_charge,
+            ionic_repulsion,
+        })
+    }
+}
+
+impl Function<f64> for PeriodicIonicPotential {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64, Error> {
+        let charges = Array1::from_elem(cfg.len_of(Axis(0)), -1.0);
+        Ok(self
+            .ewald
+            .interaction(cfg, &charges, &self.ion_positions, &self.ion_charge)
+            + self.ionic_repulsion)
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for PeriodicIonicPotential
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    // Madelung constant of rock salt, relative to the nearest-neighbour distance
+    const MADELUNG_NACL: f64 = 1.747_564_594_633;
+
+    #[test]
+    fn rock_salt_madelung_constant() {
+        // conventional cubic cell with four ion pairs, nearest-neighbour distance 1
+        let ewald = Ewald::new(SimulationCell::cubic(2.0).unwrap()).unwrap();
+        let positions = array![
+            [0.0, 0.0, 0.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0]
+        ];
+        let charges = array![1.0, 1.0, 1.0, 1.0, -1.0, -1.0, -1.0, -1.0];
+        let energy = ewald.energy(&positions, &charges);
+        assert!((energy + 4.0 * MADELUNG_NACL).abs() < 1e-8);
+
+        // primitive fcc cell with a single ion pair
+        let cell =
+            SimulationCell::new(array![[0.0, 1.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 0.0]]).unwrap();
+        let ewald = Ewald::new(cell.clone()).unwrap();
+        let energy = ewald.energy(
+            &array![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+            &array![1.0, -1.0],
+        );
+        assert!((energy + MADELUNG_NACL).abs() < 1e-8);
+
+        // the chloride ions as electrons
+        let v_ion =
+            PeriodicIonicPotential::new(cell.clone(), array![[0.0, 0.0, 0.0]], array![1]).unwrap();
+        let v_ee = PeriodicElectronicPotential::new(cell).unwrap();
+        let cfg = array![[1.0, 0.0, 0.0]];
+        let total = v_ion.value(&cfg).unwrap() + v_ee.value(&cfg).unwrap();
+        assert!((total + MADELUNG_NACL).abs() < 1e-8);
+    }
+
+    #[test]
+    fn simple_cubic_in_background() {
+        // unit charge in a unit cube with a uniform compensating background
+        let ewald = Ewald::new(SimulationCell::cubic(1.0).unwrap()).unwrap();
+        let energy = ewald.energy(&array![[0.3, 0.1, 0.7]], &array![1.0]);
+        assert!((energy + 0.5 * 2.837_297_479).abs() < 1e-8);
+    }
+}
@@ -0,0 +1,152 @@
+// Third party imports
+use ndarray::{Array1, Array2, Axis, ErrorKind, Ix2, ShapeError};
+// First party imports
+use crate::traits::{
+    LocalOperator,
+    OperatorValue::{self, *},
+};
+use errors::Error;
+use wavefunction_traits::{Field, Function, LogVgl};
+
+/// Coupling to a uniform static electric field $\mathbf{E}$:
+/// $\hat{V}_E = -\mathbf{E} \cdot \sum_{i=1}^{N_e} \mathbf{r}_i$.
+/// The second derivative of the energy with respect to the field strength
+/// gives the static dipole polarizability.
+#[derive(Clone)]
+pub struct ElectricField {
+    field: Array1<f64>,
+}
+
+/// Fail with a shape error if the configuration is not in `dim` dimensions.
+fn check_dimension(cfg: &Array2<f64>, dim: usize) -> Result<(), Error> {
+    if cfg.len_of(Axis(1)) == dim {
+        Ok(())
+    } else {
+        Err(ShapeError::from_kind(ErrorKind::IncompatibleShape).into())
+    }
+}
+
+impl ElectricField {
+    pub fn new(field: Array1<f64>) -> Self {
+        Self { field }
+    }
+
+    pub fn field(&self) -> &Array1<f64> {
+        &self.field
+    }
+}
+
+impl Function<f64> for ElectricField {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64, Error> {
+        check_dimension(cfg, self.field.len())?;
+        Ok(-cfg.sum_axis(Axis(0)).dot(&self.field))
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for ElectricField
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
+    }
+}
+
+/// Harmonic confinement with a frequency $\omega_d$ along each direction:
+/// $\hat{V}_{\mathrm{trap}} = \frac{1}{2}\sum_{i=1}^{N_e}\sum_d \omega_d^2 x_{i,d}^2$,
+/// e.g. for quantum dots or trapped particles of unit mass.
+#[derive(Clone)]
+pub struct HarmonicTrap {
+    frequencies: Array1<f64>,
+}
+
+impl HarmonicTrap {
+    /// Anisotropic trap, with one frequency per spatial dimension.
+    pub fn new(frequencies: Array1<f64>) -> Self {
+        Self { frequencies }
+    }
+
+    /// Isotropic trap of frequency $\omega$ in `dim` dimensions.
+    pub fn isotropic(frequency: f64, dim: usize) -> Self {
+        Self::new(Array1::from_elem(dim, frequency))
+    }
+
+    pub fn frequencies(&self) -> &Array1<f64> {
+        &self.frequencies
+    }
+}
+
+impl Function<f64> for HarmonicTrap {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64, Error> {
+        check_dimension(cfg, self.frequencies.len())?;
+        let stiffness = self.frequencies.mapv(|w| w.powi(2));
+        Ok(0.5 * (cfg * cfg).sum_axis(Axis(0)).dot(&stiffness))
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for HarmonicTrap
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn field_and_trap_values() {
+        let cfg = array![[1.0, -2.0, 0.5], [0.5, 1.0, -1.5]];
+        let field = ElectricField::new(array![0.1, 0.0, -0.2]);
+        assert!((field.value(&cfg).unwrap() - (-0.15 - 0.2)).abs() < 1e-12);
+
+        let isotropic = HarmonicTrap::isotropic(2.0, 3);
+        let r2 = (&cfg * &cfg).sum();
+        assert!((isotropic.value(&cfg).unwrap() - 2.0 * r2).abs() < 1e-12);
+
+        let anisotropic = HarmonicTrap::new(array![1.0, 0.0, 3.0]);
+        let expected = 0.5 * (1.25 + 9.0 * 2.5);
+        assert!((anisotropic.value(&cfg).unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let cfg = array![[1.0, -2.0], [0.5, 1.0]];
+        match ElectricField::new(array![0.1, 0.0, -0.2]).value(&cfg) {
+            Err(Error::ShapeError(_)) => (),
+            _ => panic!("expected a shape error"),
+        }
+        match HarmonicTrap::isotropic(2.0, 3).value(&cfg) {
+            Err(Error::ShapeError(_)) => (),
+            _ => panic!("expected a shape error"),
+        }
+    }
+}
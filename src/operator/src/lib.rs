@@ -3,8 +3,16 @@
 extern crate ndarray;
 extern crate ndarray_linalg;
 
+mod algebra;
+mod ewald;
+mod field;
 mod operator;
+mod pseudopotential;
 mod traits;
 
+pub use crate::algebra::*;
+pub use crate::ewald::*;
+pub use crate::field::*;
 pub use crate::operator::*;
+pub use crate::pseudopotential::*;
 pub use crate::traits::*;
@@ -7,7 +7,7 @@ use crate::traits::{
     OperatorValue::{self, *},
 };
 use errors::Error;
-use wavefunction_traits::{Differentiate, Function};
+use wavefunction_traits::{Differentiate, Field, Function, LogVgl};
 
 /// Ionic potential energy operator:
 /// $\hat{V}_{\mathrm{ion}} = -\sum_{i=1}^{N_{\mathrm{ions}}\sum_{j=1}^{\mathrm{e}} \frac{Z_i}{r_{ij}}$.
@@ -55,9 +55,22 @@ impl IonicPotential {
     }
 }
 
-impl<T: Function<f64, D = Ix2>> LocalOperator<T> for IonicPotential {
+impl<T, F> LocalOperator<T, F> for IonicPotential
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
-        Ok(Scalar(self.value(cfg)? * wf.value(cfg)?))
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
     }
 }
 
@@ -90,37 +103,121 @@ impl Function<f64> for ElectronicPotential {
     }
 }
 
-impl<T: Function<f64, D = Ix2>> LocalOperator<T> for ElectronicPotential {
+impl<T, F> LocalOperator<T, F> for ElectronicPotential
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
+    }
+}
+
+/// Lennard-Jones pair potential between identical particles:
+/// $\hat{V}_{LJ} = \sum_{i<j} 4\epsilon \left[ (\sigma/r_{ij})^{12} - (\sigma/r_{ij})^6 \right]$,
+/// e.g. for clusters of helium atoms.
+#[derive(Copy, Clone)]
+pub struct LennardJonesPotential {
+    epsilon: f64,
+    sigma: f64,
+}
+
+impl LennardJonesPotential {
+    pub fn new(epsilon: f64, sigma: f64) -> Self {
+        Self { epsilon, sigma }
+    }
+}
+
+impl Function<f64> for LennardJonesPotential {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64, Error> {
+        let num_particles = cfg.len_of(Axis(0));
+        let mut pot = 0.;
+        for i in 0..num_particles {
+            for j in i + 1..num_particles {
+                let separation = &cfg.slice(s![i, ..]) - &cfg.slice(s![j, ..]);
+                let x6 = (self.sigma / separation.norm_l2()).powi(6);
+                pot += 4. * self.epsilon * (x6.powi(2) - x6);
+            }
+        }
+        Ok(pot)
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for LennardJonesPotential
+where
+    T: Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
-        Ok(Scalar(self.value(cfg)? * wf.value(cfg)?))
+        Ok(wf.value(cfg)?.mul_real(self.value(cfg)?).into())
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        Ok(Scalar(self.value(cfg)?))
     }
 }
 
 /// Kinetic energy operator:
-/// $\hat{T} = -\frac{1}{2}\sum_{i=1}^{N_e}\nabla_{i}^2$.
-/// The kinetic energy is solely a function of the current electronic configuration,
-/// and so it is not parametrized over anything.
+/// $\hat{T} = -\frac{1}{2m}\sum_{i=1}^{N_e}\nabla_{i}^2$.
+/// The particle mass $m$ is unity for electrons in atomic units; heavier
+/// particles, such as helium atoms, are described with `with_mass`.
 #[derive(Copy, Clone)]
-pub struct KineticEnergy {}
+pub struct KineticEnergy {
+    mass: f64,
+}
 
 impl Default for KineticEnergy {
     fn default() -> Self {
-        KineticEnergy {}
+        Self::new()
     }
 }
 
 impl KineticEnergy {
     pub fn new() -> Self {
-        KineticEnergy {}
+        KineticEnergy { mass: 1.0 }
+    }
+
+    pub fn with_mass(mass: f64) -> Self {
+        KineticEnergy { mass }
     }
 }
 
-impl<T> LocalOperator<T> for KineticEnergy
+impl<T, F> LocalOperator<T, F> for KineticEnergy
 where
-    T: Differentiate<D = Ix2>,
+    T: Differentiate<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
 {
     fn act_on(&self, wf: &T, cfg: &Array<f64, Ix2>) -> Result<OperatorValue, Error> {
-        Ok(Scalar(-0.5 * wf.laplacian(cfg)?))
+        Ok(wf.laplacian(cfg)?.mul_real(-0.5 / self.mass).into())
+    }
+
+    /// $-\frac{1}{2m}\nabla^2\psi/\psi = -\frac{1}{2m}(\nabla^2 \ln\psi + (\nabla \ln\psi)^2)$
+    fn local_value(
+        &self,
+        _wf: &T,
+        _cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        let (_, _, grad, laplacian) = log_vgl;
+        Ok((*laplacian + (grad * grad).sum())
+            .mul_real(-0.5 / self.mass)
+            .into())
     }
 }
 
@@ -139,12 +236,22 @@ impl IonicHamiltonian {
     }
 }
 
-impl<T> LocalOperator<T> for IonicHamiltonian
+impl<T, F> LocalOperator<T, F> for IonicHamiltonian
 where
-    T: Differentiate<D = Ix2> + Function<f64, D = Ix2>,
+    T: Differentiate<F, D = Ix2> + Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
 {
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
-        Ok(self.t.act_on(wf, cfg)? + self.v.act_on(wf, cfg)?)
+        self.t.act_on(wf, cfg)? + self.v.act_on(wf, cfg)?
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        self.t.local_value(wf, cfg, log_vgl)? + self.v.local_value(wf, cfg, log_vgl)?
     }
 }
 
@@ -167,18 +274,141 @@ impl ElectronicHamiltonian {
 
     pub fn from_ions(ion_pos: Array2<f64>, ion_charge: Array1<i32>) -> Self {
         Self {
-            t: KineticEnergy {},
+            t: KineticEnergy::new(),
             vion: IonicPotential::new(ion_pos, ion_charge),
             velec: ElectronicPotential {},
         }
     }
 }
 
-impl<T> LocalOperator<T> for ElectronicHamiltonian
+impl<T, F> LocalOperator<T, F> for ElectronicHamiltonian
 where
-    T: Differentiate<D = Ix2> + Function<f64, D = Ix2>,
+    T: Differentiate<F, D = Ix2> + Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
 {
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
-        Ok(self.t.act_on(wf, cfg)? + self.vion.act_on(wf, cfg)? + self.velec.act_on(wf, cfg)?)
+        (self.t.act_on(wf, cfg)? + self.vion.act_on(wf, cfg)?)? + self.velec.act_on(wf, cfg)?
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        (self.t.local_value(wf, cfg, log_vgl)? + self.vion.local_value(wf, cfg, log_vgl)?)?
+            + self.velec.local_value(wf, cfg, log_vgl)?
+    }
+}
+
+/// Hamiltonian of a cluster of identical particles of mass $m$ interacting
+/// through a Lennard-Jones potential:
+/// $\hat{H} = \hat{T} + \hat{V}_{LJ}$.
+#[derive(Clone)]
+pub struct LennardJonesHamiltonian {
+    t: KineticEnergy,
+    v: LennardJonesPotential,
+}
+
+impl LennardJonesHamiltonian {
+    pub fn new(t: KineticEnergy, v: LennardJonesPotential) -> Self {
+        LennardJonesHamiltonian { t, v }
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for LennardJonesHamiltonian
+where
+    T: Differentiate<F, D = Ix2> + Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        self.t.act_on(wf, cfg)? + self.v.act_on(wf, cfg)?
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue, Error> {
+        self.t.local_value(wf, cfg, log_vgl)? + self.v.local_value(wf, cfg, log_vgl)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+    use ndarray_linalg::c64;
+
+    // complex plane wave $e^{i\mathbf{k}\cdot\mathbf{r}}$ for a single electron
+    struct PlaneWave {
+        k: Array1<f64>,
+    }
+
+    impl Function<c64> for PlaneWave {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<c64, Error> {
+            Ok(c64::new(0.0, self.k.dot(&cfg.row(0))).exp())
+        }
+    }
+
+    impl Differentiate<c64> for PlaneWave {
+        type D = Ix2;
+
+        fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<c64>, Error> {
+            let value = self.value(cfg)?;
+            let ik = self.k.mapv(|k| c64::new(0.0, k) * value);
+            Ok(ik.into_shape((1, 3)).unwrap())
+        }
+
+        fn laplacian(&self, cfg: &Array2<f64>) -> Result<c64, Error> {
+            Ok(-self.k.dot(&self.k) * self.value(cfg)?)
+        }
+    }
+
+    #[test]
+    fn complex_kinetic_energy() {
+        let wf = PlaneWave {
+            k: array![0.3, -1.2, 0.5],
+        };
+        let cfg = array![[0.4, 0.1, -0.7]];
+        let expected = c64::new(0.5 * wf.k.dot(&wf.k), 0.0);
+        let t = KineticEnergy::new();
+
+        let log_vgl = wf.log_vgl(&cfg).unwrap();
+        let local = t.local_value(&wf, &cfg, &log_vgl).unwrap();
+        assert!((local.get_complex().unwrap() - expected).norm() < 1e-12);
+
+        let direct =
+            (t.act_on(&wf, &cfg).unwrap() / OperatorValue::from(wf.value(&cfg).unwrap())).unwrap();
+        assert!((direct.get_complex().unwrap() - expected).norm() < 1e-12);
+    }
+
+    #[test]
+    fn kinetic_energy_scales_with_mass() {
+        let wf = PlaneWave {
+            k: array![0.3, -1.2, 0.5],
+        };
+        let cfg = array![[0.4, 0.1, -0.7]];
+        let log_vgl = wf.log_vgl(&cfg).unwrap();
+        let light = KineticEnergy::new()
+            .local_value(&wf, &cfg, &log_vgl)
+            .unwrap();
+        let heavy = KineticEnergy::with_mass(4.0)
+            .local_value(&wf, &cfg, &log_vgl)
+            .unwrap();
+        assert!((light.get_complex().unwrap() - 4.0 * heavy.get_complex().unwrap()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn lennard_jones_minimum() {
+        let v = LennardJonesPotential::new(1.5, 2.0);
+        let r_min = 2.0 * 2f64.powf(1.0 / 6.0);
+        let cfg = array![[0.0, 0.0, 0.0], [r_min, 0.0, 0.0]];
+        assert!((v.value(&cfg).unwrap() + 1.5).abs() < 1e-12);
+        let cfg = array![[0.0, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 50.0]];
+        assert!(v.value(&cfg).unwrap().abs() < 1e-6);
     }
 }
@@ -0,0 +1,598 @@
+// Standard imports
+use std::fs;
+use std::path::Path;
+// Third party imports
+use ndarray::{Array1, Array2, Axis, Ix2};
+use ndarray_linalg::Norm;
+// First party imports
+use crate::operator::{ElectronicPotential, IonicPotential, KineticEnergy};
+use crate::traits::{LocalOperator, OperatorValue};
+use errors::Error::{self, ParseError};
+use wavefunction_traits::{Differentiate, Field, Function, LogVgl, WaveFunction};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Burkatzki-Filippi-Dolg pseudopotentials bundled with Mole, in GAMESS format.
+pub const BFD_PSEUDOPOTENTIALS: &str = include_str!("../pseudopotentials/bfd.gms");
+
+/// Radial part $v(r) = \sum_k c_k r^{n_k - 2} e^{-\zeta_k r^2}$ of a
+/// pseudopotential channel, with terms $(c_k, n_k, \zeta_k)$ as tabulated
+/// in GAMESS format.
+#[derive(Clone, Debug)]
+pub struct RadialPotential {
+    terms: Vec<(f64, i32, f64)>,
+}
+
+impl RadialPotential {
+    pub fn new(terms: Vec<(f64, i32, f64)>) -> Self {
+        Self { terms }
+    }
+
+    pub fn value(&self, r: f64) -> f64 {
+        self.terms
+            .iter()
+            .map(|&(c, n, zeta)| c * r.powi(n - 2) * (-zeta * r.powi(2)).exp())
+            .sum()
+    }
+
+    pub fn terms(&self) -> &[(f64, i32, f64)] {
+        &self.terms
+    }
+}
+
+/// Semilocal pseudopotential of a single ion,
+/// $\hat{V} = v_L(r) + \sum_{l=0}^{L-1} v_l(r) \sum_m |lm\rangle\langle lm|$,
+/// replacing its `core_electrons` innermost electrons. The local channel $v_L$
+/// excludes the Coulomb term $-Z_{\mathrm{eff}}/r$ of the remaining ionic
+/// charge, which is left to `IonicPotential`.
+#[derive(Clone, Debug)]
+pub struct Pseudopotential {
+    name: String,
+    core_electrons: i32,
+    local: RadialPotential,
+    nonlocal: Vec<RadialPotential>,
+}
+
+fn parse_error(msg: &str, line: &str) -> Error {
+    ParseError(format!("{}: '{}'", msg, line.trim()))
+}
+
+/// Parse a floating point number, allowing Fortran-style exponents such as `1.0D-02`.
+fn parse_float(token: &str) -> Result<f64> {
+    token
+        .replace('D', "E")
+        .replace('d', "e")
+        .parse()
+        .map_err(|_| parse_error("Invalid number", token))
+}
+
+fn parse_int<T: std::str::FromStr>(token: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| parse_error("Invalid integer", token))
+}
+
+impl Pseudopotential {
+    /// Pseudopotential with local channel `local` and nonlocal channels
+    /// `nonlocal`, ordered by angular momentum $l = 0, 1, \dots$
+    pub fn new(
+        name: &str,
+        core_electrons: i32,
+        local: RadialPotential,
+        nonlocal: Vec<RadialPotential>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            core_electrons,
+            local,
+            nonlocal,
+        }
+    }
+
+    /// Absence of a pseudopotential, for ions treated with all electrons.
+    pub fn none(name: &str) -> Self {
+        Self::new(name, 0, RadialPotential::new(Vec::new()), Vec::new())
+    }
+
+    /// Read all pseudopotentials in a file in GAMESS format.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Vec<Self>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// The bundled Burkatzki-Filippi-Dolg pseudopotential of `element`, e.g. `"C"`.
+    pub fn bfd(element: &str) -> Result<Self> {
+        let name = format!("{}-QMC", element);
+        Self::parse(BFD_PSEUDOPOTENTIALS)?
+            .into_iter()
+            .find(|pp| pp.name == name)
+            .ok_or_else(|| ParseError(format!("No bundled pseudopotential for {}", element)))
+    }
+
+    /// Parse pseudopotentials in the format of the GAMESS `$ECP` group, e.g.
+    /// as distributed with the Burkatzki-Filippi-Dolg tables. Each entry
+    /// starts with a header `NAME GEN NCORE LMAX`, followed by the local
+    /// channel and the channels $l = 0, \dots, L - 1$, each given by its number
+    /// of terms and one line `c n zeta` per term. A header `NAME NONE` denotes
+    /// an all-electron ion, and a header `NAME` repeats an earlier entry.
+    pub fn parse(contents: &str) -> Result<Vec<Self>> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !(line.is_empty() || line.starts_with('!') || line.starts_with('$')));
+        let mut pseudopotentials: Vec<Self> = Vec::new();
+        while let Some(header) = lines.next() {
+            let tokens: Vec<&str> = header.split_whitespace().collect();
+            let name = tokens[0];
+            let kind = tokens.get(1).map(|kind| kind.to_uppercase());
+            let pseudopotential = match kind.as_deref() {
+                Some("GEN") => {
+                    if tokens.len() < 4 {
+                        return Err(parse_error("Expected NAME GEN NCORE LMAX", header));
+                    }
+                    let core_electrons = parse_int(tokens[2])?;
+                    let lmax: usize = parse_int(tokens[3])?;
+                    let mut channels = (0..=lmax)
+                        .map(|_| Self::parse_channel(&mut lines))
+                        .collect::<Result<Vec<_>>>()?;
+                    let local = channels.remove(0);
+                    Self::new(name, core_electrons, local, channels)
+                }
+                Some("NONE") => Self::none(name),
+                Some(_) => return Err(parse_error("Unknown pseudopotential type", header)),
+                None => pseudopotentials
+                    .iter()
+                    .find(|pp| pp.name == name)
+                    .cloned()
+                    .ok_or_else(|| parse_error("Undefined pseudopotential", header))?,
+            };
+            pseudopotentials.push(pseudopotential);
+        }
+        Ok(pseudopotentials)
+    }
+
+    fn parse_channel<'a, I>(lines: &mut I) -> Result<RadialPotential>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let end = || ParseError("Unexpected end of pseudopotential".to_string());
+        let header = lines.next().ok_or_else(end)?;
+        let num_terms: usize = parse_int(header.split_whitespace().next().unwrap_or(""))?;
+        let mut terms = Vec::with_capacity(num_terms);
+        for _ in 0..num_terms {
+            let line = lines.next().ok_or_else(end)?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() < 3 {
+                return Err(parse_error(
+                    "Expected coefficient, power and exponent",
+                    line,
+                ));
+            }
+            terms.push((
+                parse_float(tokens[0])?,
+                parse_int(tokens[1])?,
+                parse_float(tokens[2])?,
+            ));
+        }
+        Ok(RadialPotential::new(terms))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn core_electrons(&self) -> i32 {
+        self.core_electrons
+    }
+
+    pub fn local(&self) -> &RadialPotential {
+        &self.local
+    }
+
+    /// Nonlocal channels $v_l$, ordered by angular momentum.
+    pub fn nonlocal(&self) -> &[RadialPotential] {
+        &self.nonlocal
+    }
+}
+
+/// Quadrature rule on the unit sphere used to project out angular momenta.
+#[derive(Clone, Copy, Debug)]
+pub enum Quadrature {
+    /// Six vertices of an octahedron, exact for spherical harmonics up to $l = 3$.
+    Octahedron,
+    /// Twelve vertices of an icosahedron, exact up to $l = 5$.
+    Icosahedron,
+}
+
+impl Quadrature {
+    /// Points on the unit sphere, as rows, and their weights, which sum to one.
+    fn grid(self) -> (Array2<f64>, Array1<f64>) {
+        let points = match self {
+            Quadrature::Octahedron => array![
+                [1.0, 0.0, 0.0],
+                [-1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, -1.0, 0.0],
+                [0.0, 0.0, 1.0],
+                [0.0, 0.0, -1.0]
+            ],
+            Quadrature::Icosahedron => {
+                let phi = 0.5 * (1.0 + 5f64.sqrt());
+                let mut points = Array2::zeros((12, 3));
+                let mut n = 0;
+                for axis in 0..3 {
+                    for &a in &[1.0, -1.0] {
+                        for &b in &[phi, -phi] {
+                            points[[n, (axis + 1) % 3]] = a;
+                            points[[n, (axis + 2) % 3]] = b;
+                            n += 1;
+                        }
+                    }
+                }
+                points / (1.0 + phi.powi(2)).sqrt()
+            }
+        };
+        let num_points = points.len_of(Axis(0));
+        (
+            points,
+            Array1::from_elem(num_points, 1.0 / num_points as f64),
+        )
+    }
+}
+
+/// Legendre polynomial $P_l(x)$, by the Bonnet recursion.
+fn legendre(l: usize, x: f64) -> f64 {
+    let (mut p0, mut p1) = (1.0, x);
+    if l == 0 {
+        return p0;
+    }
+    for n in 1..l {
+        let p2 = ((2 * n + 1) as f64 * x * p1 - n as f64 * p0) / (n + 1) as f64;
+        p0 = p1;
+        p1 = p2;
+    }
+    p1
+}
+
+/// Pseudopotential energy operator of a set of ions, one `Pseudopotential`
+/// per ion. The nonlocal channels act on the wave function through the
+/// angular integral
+/// $\frac{2l + 1}{4\pi} \int d\Omega' P_l(\hat{r} \cdot \hat{r}')
+/// \psi(\dots, \mathbf{r}', \dots) / \psi(\dots, \mathbf{r}, \dots)$
+/// for each electron, evaluated by a `Quadrature` over `WaveFunction::ratio`.
+/// Wave functions with cached state should therefore be up to date with the
+/// configuration, as they are during sampling.
+#[derive(Clone)]
+pub struct SemilocalPotential {
+    ion_positions: Array2<f64>,
+    pseudopotentials: Vec<Pseudopotential>,
+    quadrature: Quadrature,
+}
+
+impl SemilocalPotential {
+    /// Electron-ion pairs are skipped when all nonlocal channels are smaller than this.
+    const CUTOFF: f64 = 1e-10;
+
+    pub fn new(ion_positions: Array2<f64>, pseudopotentials: Vec<Pseudopotential>) -> Self {
+        assert_eq!(ion_positions.len_of(Axis(0)), pseudopotentials.len());
+        Self {
+            ion_positions,
+            pseudopotentials,
+            quadrature: Quadrature::Icosahedron,
+        }
+    }
+
+    /// Set the quadrature for the nonlocal channels, an icosahedron by default.
+    pub fn with_quadrature(mut self, quadrature: Quadrature) -> Self {
+        self.quadrature = quadrature;
+        self
+    }
+
+    pub fn pseudopotentials(&self) -> &[Pseudopotential] {
+        &self.pseudopotentials
+    }
+
+    /// Sum of the local channels over all electron-ion pairs.
+    fn local_part(&self, cfg: &Array2<f64>) -> f64 {
+        let mut pot = 0.0;
+        for (center, pp) in self.ion_positions.outer_iter().zip(&self.pseudopotentials) {
+            for electron in cfg.outer_iter() {
+                pot += pp.local.value((&electron - &center).norm_l2());
+            }
+        }
+        pot
+    }
+
+    /// Local value $\hat{V}_{NL}\psi/\psi$ of the nonlocal channels.
+    fn nonlocal_part<T, F>(&self, wf: &T, cfg: &Array2<f64>) -> Result<F>
+    where
+        T: WaveFunction<F> + Function<F, D = Ix2>,
+        F: Field,
+    {
+        let (points, weights) = self.quadrature.grid();
+        let mut value = F::from_f64(0.0);
+        for (center, pp) in self.ion_positions.outer_iter().zip(&self.pseudopotentials) {
+            if pp.nonlocal.is_empty() {
+                continue;
+            }
+            for (idx, electron) in cfg.outer_iter().enumerate() {
+                let separation = &electron - &center;
+                let r = separation.norm_l2();
+                let channels: Vec<f64> = pp.nonlocal.iter().map(|v| v.value(r)).collect();
+                if channels.iter().all(|v| v.abs() < Self::CUTOFF) {
+                    continue;
+                }
+                let direction = separation / r;
+                for (point, &weight) in points.outer_iter().zip(weights.iter()) {
+                    let cos = direction.dot(&point);
+                    let projection: f64 = channels
+                        .iter()
+                        .enumerate()
+                        .map(|(l, v)| (2 * l + 1) as f64 * v * legendre(l, cos))
+                        .sum();
+                    let pos = &center + &(r * &point);
+                    value = value + wf.ratio(cfg, &pos, idx)?.mul_real(weight * projection);
+                }
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for SemilocalPotential
+where
+    T: WaveFunction<F> + Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        let local = self.nonlocal_part(wf, cfg)?.add_real(self.local_part(cfg));
+        Ok((wf.value(cfg)? * local).into())
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue> {
+        Ok(self
+            .nonlocal_part(wf, cfg)?
+            .add_real(self.local_part(cfg))
+            .into())
+    }
+}
+
+/// Electronic Hamiltonian with pseudopotentials:
+/// $\hat{H} = \hat{T} + \hat{V}_{\mathrm{ion}} + \hat{V}_{\mathrm{ee}} + \hat{V}_{\mathrm{PP}}$,
+/// where $\hat{V}_{\mathrm{ion}}$ is the Coulomb potential of the ions with
+/// their core charge removed.
+#[derive(Clone)]
+pub struct PseudopotentialHamiltonian {
+    t: KineticEnergy,
+    vion: IonicPotential,
+    velec: ElectronicPotential,
+    vpp: SemilocalPotential,
+}
+
+impl PseudopotentialHamiltonian {
+    pub fn new(
+        t: KineticEnergy,
+        vion: IonicPotential,
+        velec: ElectronicPotential,
+        vpp: SemilocalPotential,
+    ) -> Self {
+        Self {
+            t,
+            vion,
+            velec,
+            vpp,
+        }
+    }
+
+    /// Hamiltonian of ions with nuclear charges `ion_charge`, each with a
+    /// pseudopotential replacing its core electrons.
+    pub fn from_ions(
+        ion_pos: Array2<f64>,
+        ion_charge: Array1<i32>,
+        pseudopotentials: Vec<Pseudopotential>,
+    ) -> Self {
+        let core_electrons: Array1<i32> = pseudopotentials
+            .iter()
+            .map(|pp| pp.core_electrons)
+            .collect();
+        Self {
+            t: KineticEnergy::new(),
+            vion: IonicPotential::new(ion_pos.clone(), ion_charge - core_electrons),
+            velec: ElectronicPotential::new(),
+            vpp: SemilocalPotential::new(ion_pos, pseudopotentials),
+        }
+    }
+}
+
+impl<T, F> LocalOperator<T, F> for PseudopotentialHamiltonian
+where
+    T: WaveFunction<F> + Differentiate<F, D = Ix2> + Function<F, D = Ix2>,
+    F: Field + Into<OperatorValue>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        vec![
+            self.t.act_on(wf, cfg)?,
+            self.vion.act_on(wf, cfg)?,
+            self.velec.act_on(wf, cfg)?,
+            self.vpp.act_on(wf, cfg)?,
+        ]
+        .into_iter()
+        .sum()
+    }
+
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue> {
+        vec![
+            self.t.local_value(wf, cfg, log_vgl)?,
+            self.vion.local_value(wf, cfg, log_vgl)?,
+            self.velec.local_value(wf, cfg, log_vgl)?,
+            self.vpp.local_value(wf, cfg, log_vgl)?,
+        ]
+        .into_iter()
+        .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // made-up coefficients, only to exercise the parser
+    const ECP: &str = "
+ $ECP
+X-ECP GEN 2 1
+2      ----- p-ul potential -----
+  4.00000000 1 8.00000000
+ -3.00000000 2 2.00000000
+1      ----- s-ul potential -----
+  5.0D+00 2 1.5D+00
+H NONE
+X-ECP
+ $END
+";
+
+    #[test]
+    fn parse_gamess_format() {
+        let pps = Pseudopotential::parse(ECP).unwrap();
+        assert_eq!(pps.len(), 3);
+        assert_eq!(pps[0].name(), "X-ECP");
+        assert_eq!(pps[0].core_electrons(), 2);
+        assert_eq!(pps[0].nonlocal().len(), 1);
+        let r: f64 = 0.7;
+        let local = 4.0 / r * (-8.0 * r * r).exp() - 3.0 * (-2.0 * r * r).exp();
+        assert!((pps[0].local().value(r) - local).abs() < 1e-14);
+        assert!((pps[0].nonlocal()[0].value(r) - 5.0 * (-1.5 * r * r).exp()).abs() < 1e-14);
+        assert_eq!(pps[1].core_electrons(), 0);
+        assert!(pps[1].nonlocal().is_empty());
+        assert_eq!(pps[2].local().terms(), pps[0].local().terms());
+
+        assert!(Pseudopotential::parse("Y-ECP GEN 2 1\n1\n1.0 2 1.0\n").is_err());
+        assert!(Pseudopotential::parse("Y-ECP\n").is_err());
+    }
+
+    // single electron in an s- or p-orbital centered on the ion
+    struct Orbital {
+        l: usize,
+        center: Array1<f64>,
+    }
+
+    impl Function<f64> for Orbital {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+            let x = &cfg.row(0) - &self.center;
+            let radial = (-x.dot(&x)).exp();
+            Ok(if self.l == 0 { radial } else { x[2] * radial })
+        }
+    }
+
+    impl Differentiate for Orbital {
+        type D = Ix2;
+
+        fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+            let x = &cfg.row(0) - &self.center;
+            let radial = (-x.dot(&x)).exp();
+            let mut grad = -2.0 * self.value(cfg)? * &x;
+            if self.l == 1 {
+                grad[2] += radial;
+            }
+            Ok(grad.into_shape((1, 3))?)
+        }
+
+        fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+            let x = &cfg.row(0) - &self.center;
+            let r2 = x.dot(&x);
+            Ok(self.value(cfg)? * (4.0 * r2 - 6.0 - 4.0 * self.l as f64))
+        }
+    }
+
+    impl WaveFunction for Orbital {
+        fn num_electrons(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn bundled_carbon_pseudopotential() {
+        let pp = Pseudopotential::bfd("C").unwrap();
+        assert_eq!(pp.core_electrons(), 2);
+        assert_eq!(pp.nonlocal().len(), 1);
+        // the local channel cancels the Coulomb singularity of the valence charge
+        let r = 1e-8;
+        assert!((pp.local().value(r) - 4.0 / r + 19.17537323).abs() < 1e-6);
+        assert!(Pseudopotential::bfd("Xx").is_err());
+
+        // an electron in an s-orbital on the ion only feels the s-channel
+        let center = array![0.1, -0.2, 0.3];
+        let h = PseudopotentialHamiltonian::from_ions(
+            center.clone().into_shape((1, 3)).unwrap(),
+            array![6],
+            vec![pp.clone()],
+        );
+        let wf = Orbital { l: 0, center };
+        let cfg = array![[0.5, 0.4, -0.6]];
+        let r = (&cfg.row(0) - &wf.center).norm_l2();
+        let log_vgl = wf.log_vgl(&cfg).unwrap();
+        let energy = *h
+            .local_value(&wf, &cfg, &log_vgl)
+            .unwrap()
+            .get_scalar()
+            .unwrap();
+        let kinetic = 3.0 - 2.0 * r.powi(2);
+        let expected = kinetic - 4.0 / r + pp.local().value(r) + pp.nonlocal()[0].value(r);
+        assert!((energy - expected).abs() < 1e-10);
+    }
+
+    #[test]
+    fn every_bundled_pseudopotential_loads() {
+        let bundled = Pseudopotential::parse(BFD_PSEUDOPOTENTIALS).unwrap();
+        assert!(!bundled.is_empty());
+        for pp in bundled {
+            let element = pp.name().trim_end_matches("-QMC");
+            let loaded = Pseudopotential::bfd(element).unwrap();
+            assert_eq!(loaded.core_electrons(), pp.core_electrons());
+            assert!(!loaded.nonlocal().is_empty());
+            // the first two local terms $Z e^{-\alpha r^2}/r + Z\alpha r e^{-\beta r^2}$
+            // make the potential smooth at the ion, including the Coulomb term
+            let terms = loaded.local().terms();
+            let (charge, n, alpha) = terms[0];
+            assert_eq!((n, terms[1].1), (1, 3));
+            assert!((terms[1].0 - charge * alpha).abs() < 1e-6 * terms[1].0);
+        }
+    }
+
+    #[test]
+    fn projects_angular_momentum() {
+        let center = array![0.1, -0.2, 0.3];
+        let s = RadialPotential::new(vec![(2.0, 2, 1.0)]);
+        let p = RadialPotential::new(vec![(-3.0, 2, 0.5)]);
+        let pp = Pseudopotential::new("X", 2, RadialPotential::new(Vec::new()), vec![s, p]);
+        let cfg = array![[0.5, 0.4, -0.6]];
+        let r = (&cfg.row(0) - &center).norm_l2();
+        for &quadrature in &[Quadrature::Octahedron, Quadrature::Icosahedron] {
+            let v = SemilocalPotential::new(
+                center.clone().into_shape((1, 3)).unwrap(),
+                vec![pp.clone()],
+            )
+            .with_quadrature(quadrature);
+            for l in 0..2 {
+                let wf = Orbital {
+                    l,
+                    center: center.clone(),
+                };
+                let log_vgl = (0.0, 1.0, Array2::zeros((1, 3)), 0.0);
+                let local = v.local_value(&wf, &cfg, &log_vgl).unwrap();
+                let expected = pp.nonlocal()[l].value(r);
+                assert!((local.get_scalar().unwrap() - expected).abs() < 1e-12);
+            }
+        }
+    }
+}
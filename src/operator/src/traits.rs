@@ -3,8 +3,10 @@ use std::fmt;
 use std::iter::Sum;
 use std::ops::{Add, Div, Mul, Sub};
 // Third party imports
-use errors::Error::{self, OperatorValueAccessError};
-use ndarray::{Array1, Array2};
+use errors::Error::{self, OperatorValueAccessError, OperatorValueMismatch};
+use ndarray::{Array1, Array2, Ix2};
+use ndarray_linalg::c64;
+use wavefunction_traits::{Field, LogVgl};
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -13,12 +15,14 @@ pub enum OperatorValue {
     Scalar(f64),
     Vector(Array1<f64>),
     Matrix(Array2<f64>),
+    Complex(c64),
 }
 
 impl fmt::Display for OperatorValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             OperatorValue::Scalar(value) => write!(f, "{}", value),
+            OperatorValue::Complex(value) => write!(f, "{}", value),
             OperatorValue::Vector(value) => {
                 let mut output = String::new();
                 for x in value {
@@ -32,11 +36,13 @@ impl fmt::Display for OperatorValue {
 }
 
 impl OperatorValue {
-    pub fn map(&self, f: impl Fn(f64) -> f64) -> Self {
+    /// Multiplies the value by a real number.
+    pub fn scale(&self, factor: f64) -> Self {
         match self {
-            OperatorValue::Scalar(x) => OperatorValue::Scalar(f(*x)),
-            OperatorValue::Vector(v) => OperatorValue::Vector(v.mapv(f)),
-            OperatorValue::Matrix(m) => OperatorValue::Matrix(m.mapv(f)),
+            OperatorValue::Scalar(x) => OperatorValue::Scalar(x * factor),
+            OperatorValue::Vector(v) => OperatorValue::Vector(v * factor),
+            OperatorValue::Matrix(m) => OperatorValue::Matrix(m * factor),
+            OperatorValue::Complex(z) => OperatorValue::Complex(z * factor),
         }
     }
 
@@ -60,208 +66,293 @@ impl OperatorValue {
             _ => Err(OperatorValueAccessError),
         }
     }
+
+    pub fn get_complex(&self) -> Result<&c64> {
+        match self {
+            OperatorValue::Complex(value) => Ok(value),
+            _ => Err(OperatorValueAccessError),
+        }
+    }
+}
+
+impl From<f64> for OperatorValue {
+    fn from(value: f64) -> Self {
+        OperatorValue::Scalar(value)
+    }
+}
+
+impl From<c64> for OperatorValue {
+    fn from(value: c64) -> Self {
+        OperatorValue::Complex(value)
+    }
 }
 
 impl Add for OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn add(self, other: OperatorValue) -> OperatorValue {
+    fn add(self, other: OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value + value_other),
                 Vector(value_other) => Vector(value + value_other),
                 Matrix(value_other) => Matrix(value + value_other),
+                Complex(value_other) => Complex(value + value_other),
             },
             Vector(value) => match other {
                 Scalar(value_other) => Vector(value_other + value),
                 Vector(value_other) => Vector(value + value_other),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
                 Scalar(value_other) => Matrix(value_other + value),
                 Matrix(value_other) => Matrix(value_other + value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(value + value_other),
+                Complex(value_other) => Complex(value + value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Sub for OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn sub(self, other: OperatorValue) -> OperatorValue {
+    fn sub(self, other: OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value - value_other),
                 Vector(value_other) => Vector(value - value_other),
                 Matrix(value_other) => Matrix(value - value_other),
+                Complex(value_other) => Complex(value - value_other),
             },
             Vector(value) => match other {
-                Scalar(value_other) => Vector(value_other - value),
-                Vector(value_other) => Vector(value_other - value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Vector(value - value_other),
+                Vector(value_other) => Vector(value - value_other),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
-                Scalar(value_other) => Matrix(value_other - value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Matrix(value - value_other),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(value - value_other),
+                Complex(value_other) => Complex(value - value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Mul for OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn mul(self, other: OperatorValue) -> OperatorValue {
+    fn mul(self, other: OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value * value_other),
                 Vector(value_other) => Vector(value * value_other),
                 Matrix(value_other) => Matrix(value * value_other),
+                Complex(value_other) => Complex(value * value_other),
             },
             Vector(value) => match other {
                 Scalar(value_other) => Vector(value_other * value),
                 Vector(value_other) => Vector(value_other * value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
                 Scalar(value_other) => Matrix(value_other * value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(value * value_other),
+                Complex(value_other) => Complex(value * value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Div for OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn div(self, other: OperatorValue) -> OperatorValue {
+    fn div(self, other: OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value / value_other),
                 Vector(value_other) => Vector(value / value_other),
                 Matrix(value_other) => Matrix(value / value_other),
+                Complex(value_other) => Complex(value / value_other),
             },
             Vector(value) => match other {
-                Scalar(value_other) => Vector(value_other / value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Vector(value / value_other),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
-                Scalar(value_other) => Matrix(value_other / value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Matrix(value / value_other),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(value / value_other),
+                Complex(value_other) => Complex(value / value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Add for &OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn add(self, other: &OperatorValue) -> OperatorValue {
+    fn add(self, other: &OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value + value_other),
                 Vector(value_other) => Vector(*value + value_other),
                 Matrix(value_other) => Matrix(*value + value_other),
+                Complex(value_other) => Complex(*value + *value_other),
             },
             Vector(value) => match other {
                 Scalar(value_other) => Vector(*value_other + value),
                 Vector(value_other) => Vector(value_other + value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
                 Scalar(value_other) => Matrix(*value_other + value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(*value + *value_other),
+                Complex(value_other) => Complex(*value + *value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Sub for &OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn sub(self, other: &OperatorValue) -> OperatorValue {
+    fn sub(self, other: &OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value - value_other),
                 Vector(value_other) => Vector(*value - value_other),
                 Matrix(value_other) => Matrix(*value - value_other),
+                Complex(value_other) => Complex(*value - *value_other),
             },
             Vector(value) => match other {
-                Scalar(value_other) => Vector(*value_other - value),
-                Vector(value_other) => Vector(value_other - value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Vector(value - *value_other),
+                Vector(value_other) => Vector(value - value_other),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
-                Scalar(value_other) => Matrix(*value_other - value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Matrix(value - *value_other),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(*value - *value_other),
+                Complex(value_other) => Complex(*value - *value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Mul for &OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn mul(self, other: &OperatorValue) -> OperatorValue {
+    fn mul(self, other: &OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value * value_other),
                 Vector(value_other) => Vector(*value * value_other),
                 Matrix(value_other) => Matrix(*value * value_other),
+                Complex(value_other) => Complex(*value * *value_other),
             },
             Vector(value) => match other {
                 Scalar(value_other) => Vector(*value_other * value),
                 Vector(value_other) => Vector(value_other * value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
                 Scalar(value_other) => Matrix(*value_other * value),
-                _ => unimplemented!(),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(*value * *value_other),
+                Complex(value_other) => Complex(*value * *value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
 impl Div for &OperatorValue {
-    type Output = OperatorValue;
+    type Output = Result<OperatorValue>;
 
-    fn div(self, other: &OperatorValue) -> OperatorValue {
+    fn div(self, other: &OperatorValue) -> Result<OperatorValue> {
         use OperatorValue::*;
-        match self {
+        Ok(match self {
             Scalar(value) => match other {
                 Scalar(value_other) => Scalar(value / value_other),
                 Vector(value_other) => Vector(*value / value_other),
                 Matrix(value_other) => Matrix(*value / value_other),
+                Complex(value_other) => Complex(*value / *value_other),
             },
             Vector(value) => match other {
-                Scalar(value_other) => Vector(*value_other / value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Vector(value / *value_other),
+                _ => return Err(OperatorValueMismatch),
             },
             Matrix(value) => match other {
-                Scalar(value_other) => Matrix(*value_other / value),
-                _ => unimplemented!(),
+                Scalar(value_other) => Matrix(value / *value_other),
+                _ => return Err(OperatorValueMismatch),
             },
-        }
+            Complex(value) => match other {
+                Scalar(value_other) => Complex(*value / *value_other),
+                Complex(value_other) => Complex(*value / *value_other),
+                _ => return Err(OperatorValueMismatch),
+            },
+        })
     }
 }
 
-impl Sum for OperatorValue {
-    fn sum<I: Iterator<Item = OperatorValue>>(iter: I) -> OperatorValue {
-        iter.fold(OperatorValue::Scalar(0.0), |a, b| &a + &b)
+impl Sum<OperatorValue> for Result<OperatorValue> {
+    fn sum<I: Iterator<Item = OperatorValue>>(mut iter: I) -> Result<OperatorValue> {
+        iter.try_fold(OperatorValue::Scalar(0.0), |a, b| a + b)
     }
 }
 
 /// Interface for creating quantum operators that act on Function types.
-pub trait LocalOperator<T>: Send + Sync {
+/// The field `F` is that of the wave function values; complex wave functions
+/// generally yield complex local values.
+pub trait LocalOperator<T, F = f64>: Send + Sync {
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue>;
+
+    /// Local value $(\hat{O}\psi)(R)/\psi(R)$, given $\ln|\psi|$, its phase, gradient
+    /// and laplacian at $R$. Operators that only depend on these should
+    /// override this method, so that sampling does not re-evaluate the wave function.
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl<Ix2, F>,
+    ) -> Result<OperatorValue>
+    where
+        F: Field + Into<OperatorValue>,
+    {
+        let (log_value, phase, _, _) = log_vgl;
+        self.act_on(wf, cfg)? / phase.mul_real(log_value.exp()).into()
+    }
 }
 
 #[cfg(test)]
@@ -276,11 +367,31 @@ mod tests {
             let second = OperatorValue::Scalar(y);
             prop_assert_eq!(
                 x + y,
-                match &first + &second{
-                    OperatorValue::Scalar(value) => value,
+                match &first + &second {
+                    Ok(OperatorValue::Scalar(value)) => value,
                     _ => unimplemented!(),
                 }
             );
         }
     }
+
+    #[test]
+    fn divide_by_scalar() {
+        let vector = OperatorValue::Vector(Array1::from_vec(vec![2.0, -4.0]));
+        let quotient = (&vector / &OperatorValue::Scalar(2.0)).unwrap();
+        assert_eq!(quotient.get_vector().unwrap().to_vec(), vec![1.0, -2.0]);
+        let matrix = OperatorValue::Matrix(Array2::eye(2) * 3.0);
+        let quotient = (matrix / OperatorValue::Scalar(3.0)).unwrap();
+        assert_eq!(quotient.get_matrix().unwrap(), &Array2::eye(2));
+    }
+
+    #[test]
+    fn subtract_and_mismatch() {
+        let vector = OperatorValue::Vector(Array1::from_vec(vec![2.0, -4.0]));
+        let difference = (&vector - &OperatorValue::Scalar(1.0)).unwrap();
+        assert_eq!(difference.get_vector().unwrap().to_vec(), vec![1.0, -5.0]);
+        let complex = OperatorValue::Complex(c64::new(1.0, 2.0));
+        assert!((&vector + &complex).is_err());
+        assert!((vector * complex).is_err());
+    }
 }
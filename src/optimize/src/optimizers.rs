@@ -21,10 +21,11 @@ impl Optimizer for SteepestDescent {
     fn compute_parameter_update(
         &mut self,
         _pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>> {
-        let energy_grad = compute_energy_gradient(raw_data, averages)?;
+        let energy_grad = compute_energy_gradient(raw_data, averages, mask)?;
         Ok(-&(self.step_size * energy_grad))
     }
 }
@@ -50,10 +51,11 @@ impl Optimizer for MomentumDescent {
     fn compute_parameter_update(
         &mut self,
         _pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>> {
-        let energy_grad = compute_energy_gradient(raw_data, averages)?;
+        let energy_grad = compute_energy_gradient(raw_data, averages, mask)?;
         self.momentum -= &(self.step_size * energy_grad);
         Ok(self.momentum_parameter * &self.momentum)
     }
@@ -82,10 +84,11 @@ impl Optimizer for NesterovMomentum {
     fn compute_parameter_update(
         &mut self,
         _pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>> {
-        let energy_grad = compute_energy_gradient(raw_data, averages)?;
+        let energy_grad = compute_energy_gradient(raw_data, averages, mask)?;
         self.momentum_prev = self.momentum.clone();
         self.momentum = self.momentum_parameter * &self.momentum + self.step_size * energy_grad;
         Ok(-(self.momentum_parameter * &self.momentum_prev
@@ -163,10 +166,11 @@ impl Optimizer for OnlineLbfgs {
     fn compute_parameter_update(
         &mut self,
         pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>> {
-        let energy_grad = compute_energy_gradient(raw_data, averages)?;
+        let energy_grad = compute_energy_gradient(raw_data, averages, mask)?;
         self.update_curvature_pairs(&pars, &energy_grad);
         let p = self.initial_direction(&energy_grad);
         let s = -self.step_size * p;
@@ -174,7 +178,8 @@ impl Optimizer for OnlineLbfgs {
         self.grad_prev = energy_grad.clone();
         self.pars_prev = pars.clone();
         self.iter += 1;
-        Ok(s)
+        // the curvature pairs may still couple frozen and free parameters
+        Ok(zero_frozen(s, mask))
     }
 }
 
@@ -188,22 +193,17 @@ impl StochasticReconfiguration {
         Self { step_size }
     }
 
-    fn construct_sr_matrix(
-        parm_grad: &Vec<OperatorValue>,
-        wf_values: &Vec<OperatorValue>,
-    ) -> Result<Array2<f64>> {
+    fn construct_sr_matrix(parm_grad: &[OperatorValue]) -> Result<Array2<f64>> {
         let nsamples = parm_grad.len();
         let nparm = parm_grad[0].get_vector()?.len();
 
         // construct the stochastic reconfiguration matrix
         let mut sr_mat = Array2::<f64>::zeros((nparm, nparm));
 
-        // build array2 of o_i values
+        // build array2 of o_i values, the logarithmic parameter derivatives
         let mut sr_o = Array2::<f64>::zeros((nsamples, nparm));
-        for n in 0..nsamples {
-            for i in 0..nparm {
-                sr_o[[n, i]] = parm_grad[n].get_vector()?[i] / wf_values[n].get_scalar()?;
-            }
+        for (mut row, grad) in sr_o.genrows_mut().into_iter().zip(parm_grad) {
+            row.assign(grad.get_vector()?);
         }
 
         // add the <Ok Ol> term to sr_mat
@@ -237,18 +237,25 @@ impl Optimizer for StochasticReconfiguration {
     fn compute_parameter_update(
         &mut self,
         _pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>> {
-        let energy_grad = compute_energy_gradient(raw_data, averages)?;
+        let energy_grad = compute_energy_gradient(raw_data, averages, mask)?;
         let grad_parm = raw_data
             .get("Parameter gradient")
             .ok_or(Error::DataAccessError)?;
-        let wf_values = raw_data
-            .get("Wavefunction value")
-            .ok_or(Error::DataAccessError)?;
-        let sr_matrix = StochasticReconfiguration::construct_sr_matrix(grad_parm, wf_values)?;
-        Ok(self.step_size * sr_matrix.solveh_into(-0.5 * energy_grad)?)
+        // solve for the update in the subspace of free parameters only
+        let free = free_parameters(mask);
+        let sr_matrix = StochasticReconfiguration::construct_sr_matrix(grad_parm)?
+            .select(Axis(0), &free)
+            .select(Axis(1), &free);
+        let update = sr_matrix.solveh_into(-0.5 * energy_grad.select(Axis(0), &free))?;
+        let mut deltap = Array1::zeros(mask.len());
+        for (&p, du) in free.iter().zip(update.iter()) {
+            deltap[p] = self.step_size * du;
+        }
+        Ok(deltap)
     }
 }
 
@@ -262,3 +269,60 @@ fn outer_product(a: &Array1<f64>, b: &Array1<f64>) -> Array2<f64> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monte_carlo_data() -> (
+        HashMap<String, OperatorValue>,
+        HashMap<String, Vec<OperatorValue>>,
+    ) {
+        let nsamples = 50;
+        let mut parm_grad = Vec::new();
+        let mut energies = Vec::new();
+        for n in 0..nsamples {
+            let x = n as f64 / nsamples as f64;
+            parm_grad.push(OperatorValue::Vector(array![
+                x.sin(),
+                (2.0 * x).cos(),
+                x.powi(2) - 0.3
+            ]));
+            energies.push(OperatorValue::Scalar(-0.5 + (3.0 * x).sin()));
+        }
+        let energy = energies
+            .iter()
+            .map(|e| *e.get_scalar().unwrap())
+            .sum::<f64>()
+            / nsamples as f64;
+        let mut averages = HashMap::new();
+        averages.insert("Energy".to_string(), OperatorValue::Scalar(energy));
+        let mut raw_data = HashMap::new();
+        raw_data.insert("Parameter gradient".to_string(), parm_grad);
+        raw_data.insert("Energy".to_string(), energies);
+        (averages, raw_data)
+    }
+
+    #[test]
+    fn frozen_parameters_are_not_updated() {
+        let (averages, raw_data) = monte_carlo_data();
+        let pars = array![0.1, 0.2, 0.3];
+        let mask = array![true, false, true];
+        let optimizers: Vec<Box<dyn Optimizer>> = vec![
+            Box::new(SteepestDescent::new(0.1)),
+            Box::new(MomentumDescent::new(0.1, 0.5, 3)),
+            Box::new(NesterovMomentum::new(0.1, 0.5, 3)),
+            Box::new(OnlineLbfgs::new(0.1, 3, 3)),
+            Box::new(StochasticReconfiguration::new(0.1)),
+        ];
+        for mut optimizer in optimizers {
+            for _ in 0..3 {
+                let deltap = optimizer
+                    .compute_parameter_update(&pars, &mask, &averages, &raw_data)
+                    .unwrap();
+                assert_eq!(deltap[1], 0.0);
+                assert!(deltap[0] != 0.0 && deltap[2] != 0.0);
+            }
+        }
+    }
+}
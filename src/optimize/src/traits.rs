@@ -1,24 +1,18 @@
 use errors::Error;
-use ndarray::{Array1, Array2};
+use ndarray::Array1;
 use operator::OperatorValue;
 use std::collections::HashMap;
+pub use wavefunction_traits::Optimize;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub trait Optimize {
-    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>>;
-
-    fn update_parameters(&mut self, deltap: &Array1<f64>);
-
-    fn parameters(&self) -> &Array1<f64>;
-
-    fn num_parameters(&self) -> usize;
-}
-
 pub trait Optimizer {
+    /// Change in the parameters `pars`, computed from Monte Carlo data. Entries
+    /// of parameters that are frozen, i.e. `false` in `mask`, must be zero.
     fn compute_parameter_update(
         &mut self,
         pars: &Array1<f64>,
+        mask: &Array1<bool>,
         averages: &HashMap<String, OperatorValue>,
         raw_data: &HashMap<String, Vec<OperatorValue>>,
     ) -> Result<Array1<f64>>;
@@ -3,16 +3,14 @@ use ndarray::{Array1, Array2, Axis};
 use operator::OperatorValue;
 use std::collections::HashMap;
 
+/// Gradient of the energy with respect to the parameters, with the entries of
+/// frozen parameters set to zero.
 pub fn compute_energy_gradient(
     mc_data: &HashMap<String, Vec<OperatorValue>>,
     averages: &HashMap<String, OperatorValue>,
+    mask: &Array1<bool>,
 ) -> Result<Array1<f64>, Error> {
-    let wf_values = mc_data
-        .get("Wavefunction value")
-        .ok_or(DataAccessError)?
-        .iter()
-        .map(|x| x.get_scalar())
-        .collect::<Result<Vec<_>, _>>()?;
+    // logarithmic parameter derivatives of the wave function
     let wf_grad = mc_data
         .get("Parameter gradient")
         .ok_or(DataAccessError)?
@@ -32,15 +30,31 @@ pub fn compute_energy_gradient(
         .get_scalar()?;
 
     let nparm = wf_grad[0].len();
-    let nsamples = wf_values.len();
+    let nsamples = wf_grad.len();
 
     let mut local_gradient = Array2::zeros((nsamples, nparm));
 
-    for (mut row, psi, psi_i, el) in
-        izip!(local_gradient.genrows_mut(), wf_values, wf_grad, energies)
-    {
-        row += &(2.0 * &((psi_i / *psi) * (el - energy)));
+    for (mut row, o_i, el) in izip!(local_gradient.genrows_mut(), wf_grad, energies) {
+        row += &(2.0 * &(o_i * (el - energy)));
     }
 
-    Ok(local_gradient.mean_axis(Axis(0)))
+    Ok(zero_frozen(local_gradient.mean_axis(Axis(0)), mask))
+}
+
+/// Indices of the parameters that are not frozen.
+pub fn free_parameters(mask: &Array1<bool>) -> Vec<usize> {
+    mask.indexed_iter()
+        .filter(|(_, &free)| free)
+        .map(|(p, _)| p)
+        .collect()
+}
+
+/// Set the entries of frozen parameters to zero.
+pub fn zero_frozen(mut x: Array1<f64>, mask: &Array1<bool>) -> Array1<f64> {
+    x.zip_mut_with(mask, |x, &free| {
+        if !free {
+            *x = 0.0
+        }
+    });
+    x
 }
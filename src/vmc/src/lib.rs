@@ -1,5 +1,7 @@
 mod operators;
+mod polarizability;
 mod vmc;
 
+pub use crate::polarizability::*;
 pub use crate::vmc::*;
 pub use operators::*;
@@ -2,14 +2,26 @@ use errors::Error;
 use ndarray::{Array2, Ix2};
 use operator::{LocalOperator, OperatorValue};
 use optimize::Optimize;
-use wavefunction_traits::Function;
+use wavefunction_traits::{Function, LogVgl};
 
 pub struct ParameterGradient;
 
 impl<T: Optimize + Function<f64, D = Ix2>> LocalOperator<T> for ParameterGradient {
+    /// $\psi \, \partial \ln|\psi| / \partial p_k$, so that dividing by $\psi$
+    /// gives the same logarithmic derivatives as `local_value`.
     fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
-        Ok(OperatorValue::Vector(wf.parameter_gradient(cfg)?)
-            * OperatorValue::Scalar(wf.value(cfg)?))
+        OperatorValue::Vector(wf.log_parameter_gradient(cfg)?)
+            * OperatorValue::Scalar(wf.value(cfg)?)
+    }
+
+    /// Samples the logarithmic derivatives $\partial \ln|\psi| / \partial p_k$.
+    fn local_value(
+        &self,
+        wf: &T,
+        cfg: &Array2<f64>,
+        _log_vgl: &LogVgl,
+    ) -> Result<OperatorValue, Error> {
+        Ok(OperatorValue::Vector(wf.log_parameter_gradient(cfg)?))
     }
 }
 
@@ -21,4 +33,14 @@ impl<T: Function<f64, D = Ix2>> LocalOperator<T> for WavefunctionValue {
         // need to square this, since "local value" is operator product / wave function value
         Ok(OperatorValue::Scalar(wf.value(cfg)?.powi(2)))
     }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        _cfg: &Array2<f64>,
+        log_vgl: &LogVgl,
+    ) -> Result<OperatorValue, Error> {
+        let (log_value, sign, _, _) = log_vgl;
+        Ok(OperatorValue::Scalar(sign * log_value.exp()))
+    }
 }
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use errors::Error::{self, DataAccessError};
+use metropolis::Metropolis;
+use montecarlo::traits::Log;
+use montecarlo::{Runner, Sampler};
+use ndarray::{Array1, Array2, Ix2};
+use operator::{ElectricField, LocalOperator, OperatorSum, OperatorValue};
+use wavefunction_traits::{Differentiate, Function, LogVgl, WaveFunction};
+
+struct EmptyLogger;
+impl Log for EmptyLogger {
+    fn log(&mut self, _data: &HashMap<String, Vec<OperatorValue>>) -> String {
+        String::new()
+    }
+}
+
+/// Energy of a wave function $\psi'$ under a Hamiltonian $\hat{H}'$, estimated
+/// from samples of another wave function $\psi$. Samples the vector
+/// $(w, w E_L')$ with weight $w = |\psi'/\psi|^2$ and local energy
+/// $E_L' = \hat{H}'\psi'/\psi'$, so that the energy is
+/// $\langle w E_L' \rangle / \langle w \rangle$. Statistical noise that is
+/// common to both wave functions cancels in energy differences.
+pub struct CorrelatedEnergy<T, H> {
+    wave_function: T,
+    hamiltonian: H,
+}
+
+impl<T, H> CorrelatedEnergy<T, H> {
+    pub fn new(wave_function: T, hamiltonian: H) -> Self {
+        Self {
+            wave_function,
+            hamiltonian,
+        }
+    }
+}
+
+impl<T, H> LocalOperator<T> for CorrelatedEnergy<T, H>
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2> + Send + Sync,
+    H: LocalOperator<T>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue, Error> {
+        let value = self.wave_function.value(cfg)?;
+        let energy = self.hamiltonian.act_on(&self.wave_function, cfg)?;
+        OperatorValue::Vector(Array1::from_vec(vec![
+            value.powi(2),
+            value * energy.get_scalar()?,
+        ])) * OperatorValue::Scalar(1.0 / wf.value(cfg)?)
+    }
+
+    fn local_value(
+        &self,
+        _wf: &T,
+        cfg: &Array2<f64>,
+        log_vgl: &LogVgl,
+    ) -> Result<OperatorValue, Error> {
+        let other_log_vgl = self.wave_function.log_vgl(cfg)?;
+        let weight = (2.0 * (other_log_vgl.0 - log_vgl.0)).exp();
+        let energy = self
+            .hamiltonian
+            .local_value(&self.wave_function, cfg, &other_log_vgl)?;
+        Ok(OperatorValue::Vector(Array1::from_vec(vec![
+            weight,
+            weight * energy.get_scalar()?,
+        ])))
+    }
+}
+
+/// Static dipole polarizability along a field $\mathbf{E}$, from the energies
+/// at $\pm\mathbf{E}$ by the finite difference
+/// $\alpha = -\left[E(\mathbf{E}) + E(-\mathbf{E}) - 2E(0)\right] / |\mathbf{E}|^2$.
+/// The wave functions at $\pm\mathbf{E}$ are first optimized for the Hamiltonians
+/// from `hamiltonian`, e.g. with a `VmcRunner`; `run` then samples the
+/// zero-field wave function and obtains all three energies by correlated
+/// sampling.
+pub struct FiniteFieldPolarizability<H> {
+    hamiltonian: H,
+    field: Array1<f64>,
+}
+
+impl<H: Clone> FiniteFieldPolarizability<H> {
+    pub fn new(hamiltonian: H, field: Array1<f64>) -> Self {
+        Self { hamiltonian, field }
+    }
+
+    /// The zero-field Hamiltonian plus the field $\mathbf{E}$ scaled by `sign`.
+    pub fn hamiltonian(&self, sign: f64) -> OperatorSum<H, ElectricField> {
+        OperatorSum::new(
+            self.hamiltonian.clone(),
+            ElectricField::new(sign * &self.field),
+        )
+    }
+
+    /// Polarizability and its error, from `steps` correlated samples of the
+    /// wave function `zero` in blocks of `block_size`.
+    pub fn run<T, V>(
+        &self,
+        zero: T,
+        plus: T,
+        minus: T,
+        metrop: V,
+        steps: usize,
+        block_size: usize,
+    ) -> Result<(f64, f64), Error>
+    where
+        T: Function<f64, D = Ix2>
+            + Differentiate<D = Ix2>
+            + WaveFunction
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        H: LocalOperator<T> + 'static,
+        V: Metropolis<T>,
+    {
+        let mut obs: HashMap<String, Box<dyn LocalOperator<T>>> = HashMap::new();
+        obs.insert("Energy".to_string(), Box::new(self.hamiltonian.clone()));
+        obs.insert(
+            "Energy (+E)".to_string(),
+            Box::new(CorrelatedEnergy::new(plus, self.hamiltonian(1.0))),
+        );
+        obs.insert(
+            "Energy (-E)".to_string(),
+            Box::new(CorrelatedEnergy::new(minus, self.hamiltonian(-1.0))),
+        );
+        let sampler = Sampler::new(zero, metrop, &obs)?;
+        let result = Runner::new(sampler, EmptyLogger).run(steps, block_size)?;
+
+        // sums of $E_L$, and of $w$ and $w E_L$ at both fields, per block
+        let sums = |name: &str| -> Result<Vec<Array1<f64>>, Error> {
+            let samples = result.data.get(name).ok_or(DataAccessError)?;
+            samples
+                .chunks(block_size)
+                .map(|block| {
+                    block.iter().try_fold(Array1::zeros(2), |sum, x| match x {
+                        OperatorValue::Scalar(e) => Ok(sum + &Array1::from_vec(vec![1.0, *e])),
+                        OperatorValue::Vector(v) => Ok(sum + v),
+                        _ => Err(DataAccessError),
+                    })
+                })
+                .collect()
+        };
+        let (zero, plus, minus) = (sums("Energy")?, sums("Energy (+E)")?, sums("Energy (-E)")?);
+        let polarizability = |z: &Array1<f64>, p: &Array1<f64>, m: &Array1<f64>| {
+            let energy = |s: &Array1<f64>| s[1] / s[0];
+            -(energy(p) + energy(m) - 2.0 * energy(z)) / self.field.dot(&self.field)
+        };
+
+        let total = |blocks: &[Array1<f64>]| blocks.iter().fold(Array1::zeros(2), |a, b| a + b);
+        let alpha = polarizability(&total(&zero), &total(&plus), &total(&minus));
+        let block_alphas: Vec<f64> = (0..zero.len())
+            .map(|b| polarizability(&zero[b], &plus[b], &minus[b]))
+            .collect();
+        let nblocks = block_alphas.len() as f64;
+        let variance = block_alphas
+            .iter()
+            .map(|a| (a - alpha).powi(2))
+            .sum::<f64>()
+            / (nblocks - 1.0);
+        Ok((alpha, (variance / nblocks).sqrt()))
+    }
+}
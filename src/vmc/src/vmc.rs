@@ -83,7 +83,8 @@ where
             energy_errs.push(*errors["Energy"].get_scalar()?);
 
             let deltap = self.optimizer.compute_parameter_update(
-                self.sampler.wave_function().parameters(),
+                &self.sampler.wave_function().parameters(),
+                &self.sampler.wave_function().parameter_mask(),
                 &averages,
                 &mc_data,
             )?;
@@ -144,8 +145,8 @@ where
         // computes averages of all components of concatenated data
         let averages = mc_results
             .iter()
-            .map(|(name, samples)| (name.to_string(), Self::mean(&samples)))
-            .collect::<HashMap<_, _>>();
+            .map(|(name, samples)| Ok((name.to_string(), Self::mean(&samples)?)))
+            .collect::<Result<HashMap<_, _>, Error>>()?;
         // blocking error computation
         let errors = mc_results
             .iter()
@@ -158,23 +159,38 @@ where
                 let blocks = samples.chunks(block_size);
                 let nblocks = blocks.len();
                 // compute averages of each block
-                let block_means = blocks.map(Self::mean);
+                let block_means = blocks.map(Self::mean).collect::<Result<Vec<_>, Error>>()?;
                 // compute square of block averages
-                let block_mean_square =
-                    Self::mean(&block_means.clone().map(|x| &x * &x).collect::<Vec<_>>());
+                let block_mean_square = Self::mean(
+                    &block_means
+                        .iter()
+                        .map(|x| x * x)
+                        .collect::<Result<Vec<_>, Error>>()?,
+                )?;
                 // compute error
-                let error = ((block_mean_square - mean * mean) / Scalar((nblocks - 1) as f64))
-                    .map(f64::sqrt);
-                (name.to_string(), error)
+                let variance =
+                    ((block_mean_square - (mean * mean)?)? / Scalar((nblocks - 1) as f64))?;
+                Ok((name.to_string(), Self::sqrt(&variance)?))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<Result<HashMap<_, _>, Error>>()?;
         Ok((averages, errors))
     }
 
-    fn mean(vec: &[OperatorValue]) -> OperatorValue {
+    fn mean(vec: &[OperatorValue]) -> Result<OperatorValue, Error> {
         use OperatorValue::*;
-        vec.iter().enumerate().fold(Scalar(0.0), |a, (n, b)| {
-            &a + &((b - &a) / Scalar((n + 1) as f64))
+        vec.iter().enumerate().try_fold(Scalar(0.0), |a, (n, b)| {
+            &a + &((b - &a)? / Scalar((n + 1) as f64))?
         })
     }
+
+    // elementwise square root of a real value
+    fn sqrt(value: &OperatorValue) -> Result<OperatorValue, Error> {
+        use OperatorValue::*;
+        match value {
+            Scalar(x) => Ok(Scalar(x.sqrt())),
+            Vector(v) => Ok(Vector(v.mapv(f64::sqrt))),
+            Matrix(m) => Ok(Matrix(m.mapv(f64::sqrt))),
+            Complex(_) => Err(Error::OperatorValueMismatch),
+        }
+    }
 }
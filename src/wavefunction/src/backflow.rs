@@ -0,0 +1,399 @@
+// Third party imports
+use ndarray::{Array1, Array2, Array3, Ix1, Ix2};
+use ndarray_linalg::{Determinant, Inverse};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::determinant::{SlaterDeterminant, SpinDeterminantProduct};
+use crate::jastrow::monomial;
+use errors::Error;
+use wavefunction_traits::{
+    Differentiate, Function, Hessian, LogVgl, Optimize, Validate, Vgl, WaveFunction,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Backflow transformation to quasiparticle coordinates
+/// $\mathbf{x}_i = \mathbf{r}_i + \sum_{j \neq i} \eta(r_{ij}) \mathbf{r}_{ij}$,
+/// with $\mathbf{r}_{ij} = \mathbf{r}_i - \mathbf{r}_j$ and
+/// $\eta(r) = e^{-\kappa r^2} \sum_{p=0}^{N_c - 1} c_p r^{2p}$.
+/// The coefficients $c_p$ are the variational parameters; $\kappa$ is fixed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Backflow {
+    parameters: Array1<f64>,
+    kappa: f64,
+}
+
+impl Backflow {
+    pub fn new(parameters: Array1<f64>, kappa: f64) -> Self {
+        Self { parameters, kappa }
+    }
+
+    /// $\eta$ and its first two derivatives with respect to $s = r^2$.
+    fn eta(&self, s: f64) -> (f64, f64, f64) {
+        let (mut poly, mut dpoly, mut d2poly) = (0.0, 0.0, 0.0);
+        for (p, c) in self.parameters.iter().enumerate() {
+            let (sp, dsp, d2sp) = monomial(s, p as i32);
+            poly += c * sp;
+            dpoly += c * dsp;
+            d2poly += c * d2sp;
+        }
+        let k = self.kappa;
+        let exp = f64::exp(-k * s);
+        (
+            exp * poly,
+            exp * (dpoly - k * poly),
+            exp * (d2poly - 2.0 * k * dpoly + k.powi(2) * poly),
+        )
+    }
+
+    /// Quasiparticle coordinates of all electrons in configuration `cfg`.
+    pub fn quasiparticles(&self, cfg: &Array2<f64>) -> Array2<f64> {
+        let mut x = cfg.clone();
+        for (i, ri) in cfg.outer_iter().enumerate() {
+            for (_, rj) in cfg.outer_iter().enumerate().filter(|&(j, _)| j != i) {
+                let d = &ri - &rj;
+                let (eta, _, _) = self.eta(d.dot(&d));
+                x.row_mut(i).scaled_add(eta, &d);
+            }
+        }
+        x
+    }
+
+    /// Quasiparticle coordinates, the Jacobian $\partial x_{i\beta} / \partial r_{l\alpha}$
+    /// flattened to a $dN \times dN$ matrix, and the laplacians
+    /// $\sum_l \nabla_l^2 x_{i\beta}$, for $N$ electrons in $d$ dimensions.
+    fn transform(&self, cfg: &Array2<f64>) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+        let (nelec, ndim) = cfg.dim();
+        let mut x = cfg.clone();
+        let mut jacobian = Array2::<f64>::eye(nelec * ndim);
+        let mut lapl = Array2::<f64>::zeros((nelec, ndim));
+        for (i, ri) in cfg.outer_iter().enumerate() {
+            for (j, rj) in cfg.outer_iter().enumerate().filter(|&(j, _)| j != i) {
+                let d = &ri - &rj;
+                let s = d.dot(&d);
+                let (eta, deta, d2eta) = self.eta(s);
+                x.row_mut(i).scaled_add(eta, &d);
+                // $\nabla^2 (\eta \mathbf{d}) = (2(d + 2)\eta' + 4s\eta'') \mathbf{d}$,
+                // once for each of the two electrons
+                let coeff = 2.0 * (2.0 * (ndim as f64 + 2.0) * deta + 4.0 * s * d2eta);
+                lapl.row_mut(i).scaled_add(coeff, &d);
+                for beta in 0..ndim {
+                    for alpha in 0..ndim {
+                        let mut dx = 2.0 * deta * d[alpha] * d[beta];
+                        if alpha == beta {
+                            dx += eta;
+                        }
+                        jacobian[[i * ndim + beta, i * ndim + alpha]] += dx;
+                        jacobian[[i * ndim + beta, j * ndim + alpha]] -= dx;
+                    }
+                }
+            }
+        }
+        (x, jacobian, lapl)
+    }
+
+    /// Derivatives $\partial \mathbf{x}_i / \partial c_p$, indexed as `[p, i, beta]`.
+    fn parameter_derivatives(&self, cfg: &Array2<f64>) -> Array3<f64> {
+        let (nelec, ndim) = cfg.dim();
+        let mut derivs = Array3::<f64>::zeros((self.parameters.len(), nelec, ndim));
+        for (i, ri) in cfg.outer_iter().enumerate() {
+            for (_, rj) in cfg.outer_iter().enumerate().filter(|&(j, _)| j != i) {
+                let d = &ri - &rj;
+                let s = d.dot(&d);
+                let exp = f64::exp(-self.kappa * s);
+                for (p, mut deriv) in derivs.outer_iter_mut().enumerate() {
+                    deriv.row_mut(i).scaled_add(exp * s.powi(p as i32), &d);
+                }
+            }
+        }
+        derivs
+    }
+
+    pub fn parameters(&self) -> &Array1<f64> {
+        &self.parameters
+    }
+
+    pub fn kappa(&self) -> f64 {
+        self.kappa
+    }
+}
+
+/// Logarithm, sign, gradient and flattened Hessian of $\ln|D|$ for a single
+/// determinant, with respect to the quasiparticle coordinates `x`. With
+/// $M = A^{-1}$ and $P_{i\beta,j} = \sum_k \partial_\beta \phi_k(\mathbf{x}_i) M_{kj}$,
+/// $\partial_{i\beta} \partial_{j\gamma} \ln|D| = \delta_{ij} \sum_k
+/// \partial_\beta \partial_\gamma \phi_k(\mathbf{x}_i) M_{ki} - P_{i\beta,j} P_{j\gamma,i}$.
+fn determinant_hessian<T>(
+    det: &SlaterDeterminant<T>,
+    x: &Array2<f64>,
+) -> Result<(f64, f64, Array2<f64>, Array2<f64>)>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    let (nelec, ndim) = x.dim();
+    if nelec == 0 {
+        return Ok((0.0, 1.0, Array2::zeros(x.dim()), Array2::zeros((0, 0))));
+    }
+    let mut matrix = Array2::<f64>::zeros((nelec, nelec));
+    let mut orbital_grads = Array3::<f64>::zeros((nelec, ndim, nelec));
+    let mut orbital_hessians = Vec::with_capacity(nelec);
+    for (i, pos) in x.outer_iter().enumerate() {
+        let pos = pos.to_owned();
+        let mut hessians = Vec::with_capacity(nelec);
+        for (k, orbital) in det.orbitals().iter().enumerate() {
+            let (value, grad, _) = orbital.vgl(&pos)?;
+            matrix[[i, k]] = value;
+            orbital_grads.slice_mut(s![i, .., k]).assign(&grad);
+            hessians.push(orbital.hessian(&pos)?);
+        }
+        orbital_hessians.push(hessians);
+    }
+    let (sign, log_value) = matrix.sln_det()?;
+    let inverse = matrix.inv()?;
+    let p = orbital_grads
+        .into_shape((nelec * ndim, nelec))
+        .expect("Orbital gradients are contiguous")
+        .dot(&inverse);
+    let mut grad = Array2::<f64>::zeros((nelec, ndim));
+    let mut hessian = Array2::<f64>::zeros((nelec * ndim, nelec * ndim));
+    for i in 0..nelec {
+        for beta in 0..ndim {
+            grad[[i, beta]] = p[[i * ndim + beta, i]];
+        }
+        let mut block = hessian.slice_mut(s![i * ndim..(i + 1) * ndim, i * ndim..(i + 1) * ndim]);
+        for (k, orbital_hessian) in orbital_hessians[i].iter().enumerate() {
+            block.scaled_add(inverse[[k, i]], orbital_hessian);
+        }
+        for j in 0..nelec {
+            for beta in 0..ndim {
+                for gamma in 0..ndim {
+                    hessian[[i * ndim + beta, j * ndim + gamma]] -=
+                        p[[i * ndim + beta, j]] * p[[j * ndim + gamma, i]];
+                }
+            }
+        }
+    }
+    Ok((log_value, sign, grad, hessian))
+}
+
+/// Product of spin-up and spin-down Slater determinants evaluated at backflow
+/// quasiparticle coordinates, $\psi(R) = D_{\uparrow}(X) D_{\downarrow}(X)$ with
+/// $X = (\mathbf{x}_1, \dots, \mathbf{x}_N)$. Derivatives with respect to the
+/// electron coordinates follow from the chain rule,
+/// $\nabla^2 \ln|\psi| = \mathrm{tr}(H J J^T) + \sum_{i\beta} G_{i\beta} \nabla^2 x_{i\beta}$,
+/// with $G$ and $H$ the gradient and Hessian of $\ln|\psi|$ with respect to $X$,
+/// and $J$ the Jacobian of the transformation. This requires orbitals that
+/// implement `Hessian`. The variational parameters are those of the `Backflow`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackflowDeterminant<T> {
+    determinants: SpinDeterminantProduct<T>,
+    backflow: Backflow,
+}
+
+impl<T> BackflowDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    pub fn new(orbitals_up: Vec<T>, orbitals_down: Vec<T>, backflow: Backflow) -> Self {
+        Self {
+            determinants: SpinDeterminantProduct::new(orbitals_up, orbitals_down),
+            backflow,
+        }
+    }
+
+    /// Set the number of spatial dimensions of the orbitals, three by default.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.determinants = self.determinants.with_dimension(dimension);
+        self
+    }
+
+    pub fn backflow(&self) -> &Backflow {
+        &self.backflow
+    }
+
+    pub fn determinants(&self) -> &SpinDeterminantProduct<T> {
+        &self.determinants
+    }
+}
+
+impl<T> Function<f64> for BackflowDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        self.determinants.value(&self.backflow.quasiparticles(cfg))
+    }
+
+    fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+        self.determinants
+            .log_value(&self.backflow.quasiparticles(cfg))
+    }
+}
+
+impl<T> Differentiate for BackflowDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(self.vgl(cfg)?.1)
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(self.vgl(cfg)?.2)
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        let (log_value, sign, grad, lapl) = self.log_vgl(cfg)?;
+        let value = sign * log_value.exp();
+        let grad_squared = (&grad * &grad).sum();
+        Ok((value, value * grad, value * (lapl + grad_squared)))
+    }
+
+    fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+        let (nelec, ndim) = cfg.dim();
+        let nup = self.determinants.num_up();
+        let (x, jacobian, x_lapl) = self.backflow.transform(cfg);
+        let (log_up, sign_up, grad_up, hessian_up) =
+            determinant_hessian(self.determinants.up(), &x.slice(s![..nup, ..]).to_owned())?;
+        let (log_down, sign_down, grad_down, hessian_down) =
+            determinant_hessian(self.determinants.down(), &x.slice(s![nup.., ..]).to_owned())?;
+        // gradient and hessian with respect to the quasiparticle coordinates
+        let mut grad_x = Array2::<f64>::zeros((nelec, ndim));
+        grad_x.slice_mut(s![..nup, ..]).assign(&grad_up);
+        grad_x.slice_mut(s![nup.., ..]).assign(&grad_down);
+        let split = nup * ndim;
+        let mut hessian_x = Array2::<f64>::zeros((nelec * ndim, nelec * ndim));
+        hessian_x
+            .slice_mut(s![..split, ..split])
+            .assign(&hessian_up);
+        hessian_x
+            .slice_mut(s![split.., split..])
+            .assign(&hessian_down);
+
+        let grad = jacobian
+            .t()
+            .dot(&grad_x.iter().cloned().collect::<Array1<f64>>())
+            .into_shape((nelec, ndim))
+            .expect("Gradient has one entry per electron coordinate");
+        let lapl = (&hessian_x * &jacobian.dot(&jacobian.t())).sum() + (&grad_x * &x_lapl).sum();
+        Ok((log_up + log_down, sign_up * sign_down, grad, lapl))
+    }
+}
+
+impl<T> WaveFunction for BackflowDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    fn num_electrons(&self) -> usize {
+        self.determinants.num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.determinants.num_up()
+    }
+
+    fn dimension(&self) -> usize {
+        self.determinants.dimension()
+    }
+}
+
+impl<T> Optimize for BackflowDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        Ok(self.value(cfg)? * self.log_parameter_gradient(cfg)?)
+    }
+
+    /// $\partial \ln|\psi| / \partial c_p = \sum_{i\beta} G_{i\beta}
+    /// \partial x_{i\beta} / \partial c_p$.
+    fn log_parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let x = self.backflow.quasiparticles(cfg);
+        let grad_x = self.determinants.log_vgl(&x)?.2;
+        Ok(self
+            .backflow
+            .parameter_derivatives(cfg)
+            .outer_iter()
+            .map(|deriv| (&deriv * &grad_x).sum())
+            .collect())
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.backflow.parameters += deltap;
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.backflow.parameters.clone()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.backflow.parameters.len()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        (0..self.backflow.parameters.len())
+            .map(|p| format!("c_{}", p))
+            .collect()
+    }
+
+    fn parameter_groups(&self) -> Vec<String> {
+        vec![String::from("backflow"); self.backflow.parameters.len()]
+    }
+}
+
+impl Validate for Backflow {}
+
+impl<T: Validate> Validate for BackflowDeterminant<T> {
+    fn validate(&self) -> Result<()> {
+        self.determinants.validate()?;
+        self.backflow.validate()
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        self.determinants.num_particles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbitals::{AngularPart, GaussianOrbital};
+    use wavefunction_traits::check_parameter_derivatives;
+
+    fn wave_function() -> BackflowDeterminant<GaussianOrbital> {
+        let orbital = |center: Array1<f64>, angular| {
+            GaussianOrbital::new(center, array![1.1, 0.3], array![0.5, 0.6], angular)
+        };
+        let origin = array![0.0, 0.0, 0.0];
+        BackflowDeterminant::new(
+            vec![
+                orbital(origin.clone(), AngularPart::spherical(0, 0)),
+                orbital(origin.clone(), AngularPart::spherical(1, 1)),
+            ],
+            vec![orbital(
+                array![0.3, -0.2, 0.1],
+                AngularPart::spherical(1, 0),
+            )],
+            Backflow::new(array![0.3, -0.1, 0.05], 0.6),
+        )
+    }
+
+    #[test]
+    fn zero_backflow_is_identity() {
+        let cfg = array![[0.3, 0.1, -0.4], [-0.5, 0.6, 0.2], [0.1, -0.7, 0.5]];
+        let backflow = Backflow::new(Array1::zeros(3), 0.6);
+        assert!(backflow.quasiparticles(&cfg).all_close(&cfg, 1e-14));
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let wf = wave_function();
+        let cfg = array![[0.3, 0.1, -0.4], [-0.5, 0.6, 0.2], [0.1, -0.7, 0.5]];
+        let check = check_parameter_derivatives(&wf, &cfg, 1e-3).unwrap();
+        assert!(check.passes(1e-6), "{:?}", check);
+    }
+}
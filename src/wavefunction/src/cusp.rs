@@ -0,0 +1,377 @@
+// Third party imports
+use ndarray::{Array1, Array2, Ix1};
+use ndarray_linalg::Norm;
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::jastrow::monomial;
+use crate::orbitals::GaussianOrbital;
+use errors::Error;
+use wavefunction_traits::{Differentiate, Function, LinearCombination, Validate, Vgl};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Replacement of the s-type part $s(r)$ of an orbital around a single ion,
+/// by $\tilde{s}(r) = C + \sigma e^{p(r)}$ for $r < r_c$, with $p$ a polynomial
+/// of degree four.
+#[derive(Clone, Serialize, Deserialize)]
+struct CuspCorrection {
+    center: Array1<f64>,
+    radius: f64,
+    s_part: LinearCombination<GaussianOrbital>,
+    shift: f64,
+    sign: f64,
+    polynomial: [f64; 5],
+}
+
+impl CuspCorrection {
+    /// Number of radial points at which the s-part is sampled inside $r_c$.
+    const NODE_SAMPLES: usize = 100;
+    /// Number of trial values of $\alpha_0$ in the range $\ln|s(0) - C| \pm 1$.
+    const NUCLEUS_SAMPLES: usize = 200;
+
+    fn new(
+        orbital: &LinearCombination<GaussianOrbital>,
+        center: Array1<f64>,
+        charge: f64,
+        radius: f64,
+    ) -> Result<Option<Self>> {
+        let (coefficients, components): (Vec<_>, Vec<_>) = orbital
+            .coefficients()
+            .iter()
+            .zip(orbital.components())
+            .filter(|(_, basis)| {
+                basis.angular().degree() == 0 && (basis.center() - &center).norm_l2() < 1e-10
+            })
+            .map(|(&c, basis)| (c, basis.clone()))
+            .unzip();
+        if components.is_empty() {
+            return Ok(None);
+        }
+        let s_part = LinearCombination::new(Array1::from_vec(coefficients), components)?;
+
+        // radial value and derivatives of the s-part, sampled along the z-axis
+        let radial = |r: f64| -> Result<(f64, f64, f64)> {
+            let pos = &center + &(r * &array![0.0, 0.0, 1.0]);
+            let (value, grad, lapl) = s_part.vgl(&pos)?;
+            Ok((value, grad[2], lapl - 2.0 * grad[2] / r))
+        };
+        let s_zero = s_part.value(&center)?;
+        let sign = s_zero.signum();
+
+        // shift the s-part such that $s - C$ has no node inside $r_c$
+        let min = (0..=Self::NODE_SAMPLES)
+            .map(|k| radius * k as f64 / Self::NODE_SAMPLES as f64)
+            .map(|r| Ok(radial(r)?.0))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .fold(f64::INFINITY, |min, s| f64::min(min, sign * s));
+        let shift = if min > 0.0 { 0.0 } else { 1.1 * sign * min };
+
+        // match $\ln|s - C|$ and its first two derivatives at $r_c$
+        let (s_c, ds_c, d2s_c) = radial(radius)?;
+        let x2 = ds_c / (s_c - shift);
+        let boundary = (
+            f64::ln(sign * (s_c - shift)),
+            x2,
+            d2s_c / (s_c - shift) - x2.powi(2),
+        );
+        let rest = orbital.value(&center)? - s_zero;
+        let fit = |alpha0: f64| {
+            // Kato's cusp condition $\tilde{\phi}'(0) = -Z \tilde{\phi}(0)$ on the full orbital
+            let exp = sign * alpha0.exp();
+            let alpha1 = -charge * (rest + shift + exp) / exp;
+            Self::fit_polynomial(alpha0, alpha1, boundary, radius)
+        };
+
+        let mut correction = Self {
+            center,
+            radius,
+            s_part,
+            shift,
+            sign,
+            polynomial: [0.0; 5],
+        };
+        // choose the value at the nucleus for the smoothest effective local energy
+        let alpha0 = f64::ln(sign * (s_zero - shift));
+        let mut best = f64::INFINITY;
+        for k in 0..=Self::NUCLEUS_SAMPLES {
+            let candidate = alpha0 + 2.0 * k as f64 / Self::NUCLEUS_SAMPLES as f64 - 1.0;
+            let polynomial = fit(candidate);
+            let previous = std::mem::replace(&mut correction.polynomial, polynomial);
+            let deviation = correction.local_energy_deviation();
+            if deviation < best {
+                best = deviation;
+            } else {
+                correction.polynomial = previous;
+            }
+        }
+        Ok(Some(correction))
+    }
+
+    /// Solve for $\alpha_2, \alpha_3, \alpha_4$ such that $p$ and its first two
+    /// derivatives at $r_c$ equal `boundary`.
+    fn fit_polynomial(
+        alpha0: f64,
+        alpha1: f64,
+        boundary: (f64, f64, f64),
+        radius: f64,
+    ) -> [f64; 5] {
+        let (x1, x2, x3) = boundary;
+        // coefficients scaled by powers of $r_c$
+        let a = x1 - alpha0 - alpha1 * radius;
+        let b = (x2 - alpha1) * radius;
+        let c = x3 * radius.powi(2);
+        let alpha4 = (c - 4.0 * b + 6.0 * a) / 2.0;
+        let alpha3 = b - 2.0 * a - 2.0 * alpha4;
+        let alpha2 = a - alpha3 - alpha4;
+        [
+            alpha0,
+            alpha1,
+            alpha2 / radius.powi(2),
+            alpha3 / radius.powi(3),
+            alpha4 / radius.powi(4),
+        ]
+    }
+
+    /// Largest deviation of the effective one-electron local energy
+    /// $-\nabla^2 \tilde{s} / 2\tilde{s} - Z_{\mathrm{eff}}/r$ inside $r_c$ from its
+    /// value at $r_c$, where $Z_{\mathrm{eff}} = -\tilde{s}'(0)/\tilde{s}(0)$.
+    fn local_energy_deviation(&self) -> f64 {
+        let (value_zero, deriv_zero, _) = self.radial(0.0);
+        let charge = -deriv_zero / value_zero;
+        let local_energy = |r: f64| {
+            let (value, deriv, second) = self.radial(r);
+            -0.5 * (second + 2.0 * deriv / r) / value - charge / r
+        };
+        let reference = local_energy(self.radius);
+        (1..Self::NODE_SAMPLES)
+            .map(|k| local_energy(self.radius * k as f64 / Self::NODE_SAMPLES as f64))
+            .fold(0.0, |max, e| f64::max(max, (e - reference).abs()))
+    }
+
+    /// Value $\tilde{s}$, and its first and second radial derivatives.
+    fn radial(&self, r: f64) -> (f64, f64, f64) {
+        let (mut p, mut dp, mut d2p) = (0.0, 0.0, 0.0);
+        for (k, alpha) in self.polynomial.iter().enumerate() {
+            let (rk, drk, d2rk) = monomial(r, k as i32);
+            p += alpha * rk;
+            dp += alpha * drk;
+            d2p += alpha * d2rk;
+        }
+        let exp = self.sign * p.exp();
+        (self.shift + exp, exp * dp, exp * (d2p + dp.powi(2)))
+    }
+
+    /// Change in value, gradient and laplacian of the orbital at `pos`.
+    fn correction(&self, pos: &Array1<f64>) -> Result<Vgl<Ix1>> {
+        let x = pos - &self.center;
+        let r = x.norm_l2();
+        let (value, deriv, second) = self.radial(r);
+        let (s_value, s_grad, s_lapl) = self.s_part.vgl(pos)?;
+        Ok((
+            value - s_value,
+            deriv / r * x - s_grad,
+            second + 2.0 * deriv / r - s_lapl,
+        ))
+    }
+}
+
+/// Molecular orbital with a Ma-Towler-Needs cusp correction. Gaussian orbitals
+/// have zero slope at the nuclei, so that the local energy diverges there.
+/// Within a radius $r_c$ of each ion, the s-type part $s(r)$ of the orbital
+/// centered on that ion is replaced by $C + \sigma e^{p(r)}$, with
+/// $p(r) = \sum_{k=0}^{4} \alpha_k r^k$. The shift $C$ removes any node of
+/// $s(r)$ inside $r_c$, $\sigma$ is the sign of $s(0)$, and $p$ is chosen to
+/// match $s$ and its first two derivatives at $r_c$, and to satisfy Kato's
+/// cusp condition $\partial_r \tilde{\phi}(0) = -Z \tilde{\phi}(0)$, with $Z$
+/// the ion charge as given to `IonicPotential::new`. The remaining freedom, the
+/// value at the nucleus, is chosen to keep the effective local energy inside
+/// $r_c$ as flat as possible. The radius $r_c$ should be smaller than half the
+/// smallest distance between ions.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CuspCorrectedOrbital {
+    orbital: LinearCombination<GaussianOrbital>,
+    corrections: Vec<CuspCorrection>,
+}
+
+impl CuspCorrectedOrbital {
+    pub fn new(
+        orbital: LinearCombination<GaussianOrbital>,
+        ion_positions: &Array2<f64>,
+        ion_charges: &Array1<i32>,
+        radius: f64,
+    ) -> Result<Self> {
+        let mut corrections = Vec::new();
+        for (center, &charge) in ion_positions.outer_iter().zip(ion_charges.iter()) {
+            corrections.extend(CuspCorrection::new(
+                &orbital,
+                center.to_owned(),
+                f64::from(charge),
+                radius,
+            )?);
+        }
+        Ok(Self {
+            orbital,
+            corrections,
+        })
+    }
+
+    pub fn orbital(&self) -> &LinearCombination<GaussianOrbital> {
+        &self.orbital
+    }
+
+    fn correction_at(&self, pos: &Array1<f64>) -> Option<&CuspCorrection> {
+        self.corrections
+            .iter()
+            .find(|corr| (pos - &corr.center).norm_l2() < corr.radius)
+    }
+}
+
+impl Function<f64> for CuspCorrectedOrbital {
+    type D = Ix1;
+
+    fn value(&self, pos: &Array1<f64>) -> Result<f64> {
+        let value = self.orbital.value(pos)?;
+        match self.correction_at(pos) {
+            Some(corr) => Ok(value + corr.correction(pos)?.0),
+            None => Ok(value),
+        }
+    }
+}
+
+impl Differentiate for CuspCorrectedOrbital {
+    type D = Ix1;
+
+    fn gradient(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        Ok(self.vgl(pos)?.1)
+    }
+
+    fn laplacian(&self, pos: &Array1<f64>) -> Result<f64> {
+        Ok(self.vgl(pos)?.2)
+    }
+
+    fn vgl(&self, pos: &Array1<f64>) -> Result<Vgl<Ix1>> {
+        let (value, grad, lapl) = self.orbital.vgl(pos)?;
+        match self.correction_at(pos) {
+            Some(corr) => {
+                let (dvalue, dgrad, dlapl) = corr.correction(pos)?;
+                Ok((value + dvalue, grad + dgrad, lapl + dlapl))
+            }
+            None => Ok((value, grad, lapl)),
+        }
+    }
+}
+
+impl Validate for CuspCorrectedOrbital {
+    fn validate(&self) -> Result<()> {
+        self.orbital.validate()?;
+        self.corrections
+            .iter()
+            .try_for_each(|correction| correction.s_part.validate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbitals::AngularPart;
+
+    // hydrogen 1s orbital in the STO-3G basis, with a p-function on a second ion
+    fn orbital() -> LinearCombination<GaussianOrbital> {
+        LinearCombination::new(
+            array![1.0, 0.3],
+            vec![
+                GaussianOrbital::new(
+                    array![0.0, 0.0, 0.0],
+                    array![3.42525091, 0.62391373, 0.16885540],
+                    array![0.15432897, 0.53532814, 0.44463454],
+                    AngularPart::spherical(0, 0),
+                ),
+                GaussianOrbital::new(
+                    array![0.0, 0.0, 1.4],
+                    array![0.8],
+                    array![1.0],
+                    AngularPart::spherical(1, 0),
+                ),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn corrected() -> CuspCorrectedOrbital {
+        CuspCorrectedOrbital::new(
+            orbital(),
+            &array![[0.0, 0.0, 0.0], [0.0, 0.0, 1.4]],
+            &array![1, 1],
+            0.3,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn satisfies_cusp_condition() {
+        let orbital = corrected();
+        let center = array![0.0, 0.0, 0.0];
+        let value = orbital.value(&center).unwrap();
+        // spherical average of the radial derivative over the coordinate axes
+        let h = 1e-6;
+        let mut slope = 0.0;
+        for k in 0..3 {
+            let mut step = Array1::zeros(3);
+            step[k] = h;
+            slope += orbital.value(&(&center + &step)).unwrap()
+                + orbital.value(&(&center - &step)).unwrap()
+                - 2.0 * value;
+        }
+        slope /= 6.0 * h;
+        assert!((slope + value).abs() < 1e-4);
+    }
+
+    #[test]
+    fn continuous_at_radius() {
+        let orbital = corrected();
+        let inside = array![0.0, 0.3 - 1e-9, 0.0];
+        let outside = array![0.0, 0.3 + 1e-9, 0.0];
+        let (value_in, grad_in, lapl_in) = orbital.vgl(&inside).unwrap();
+        let (value_out, grad_out, lapl_out) = orbital.vgl(&outside).unwrap();
+        assert!((value_in - value_out).abs() < 1e-6);
+        assert!(grad_in.all_close(&grad_out, 1e-6));
+        assert!((lapl_in - lapl_out).abs() < 1e-6);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let orbital = corrected();
+        let pos = array![0.1, -0.05, 0.12];
+        let h = 1e-4;
+        let value = orbital.value(&pos).unwrap();
+        let (_, grad, lapl) = orbital.vgl(&pos).unwrap();
+        let mut lapl_fd = 0.0;
+        for k in 0..3 {
+            let mut step = Array1::zeros(3);
+            step[k] = h;
+            let forward = orbital.value(&(&pos + &step)).unwrap();
+            let backward = orbital.value(&(&pos - &step)).unwrap();
+            assert!((grad[k] - (forward - backward) / (2.0 * h)).abs() < 1e-6);
+            lapl_fd += (forward - 2.0 * value + backward) / h.powi(2);
+        }
+        assert!((lapl - lapl_fd).abs() < 1e-4);
+    }
+
+    #[test]
+    fn local_energy_finite_at_nucleus() {
+        // one-electron local energy $-\nabla^2\phi / 2\phi - 1/r$ near the first ion
+        let local_energy = |orbital: &dyn Fn(&Array1<f64>) -> Vgl<Ix1>, r: f64| {
+            let (value, _, lapl) = orbital(&array![r, 0.0, 0.0]);
+            -0.5 * lapl / value - 1.0 / r
+        };
+        let bare = orbital();
+        let corrected = corrected();
+        let bare_vgl = |pos: &Array1<f64>| bare.vgl(pos).unwrap();
+        let corrected_vgl = |pos: &Array1<f64>| corrected.vgl(pos).unwrap();
+        assert!(local_energy(&bare_vgl, 1e-4) < -1e3);
+        for &r in &[1e-4, 1e-2, 0.1, 0.2] {
+            assert!(local_energy(&corrected_vgl, r).abs() < 2.0);
+        }
+    }
+}
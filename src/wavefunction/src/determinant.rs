@@ -0,0 +1,588 @@
+// Third party imports
+use ndarray::{Array1, Array2, Array3, Axis, Ix1, Ix2};
+use ndarray_linalg::{Determinant, Inverse};
+use serde::{Deserialize, Serialize};
+// First party imports
+use errors::Error::{self, EmptyCacheError};
+use wavefunction_traits::{
+    check_input, Differentiate, Function, LogVgl, Optimize, Validate, Vgl, WaveFunction,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Slater determinant of single-particle orbitals:
+/// $\psi(\mathbf{r}_1, \dots, \mathbf{r}_N) = \det(\phi_j(\mathbf{r}_i))$.
+/// The inverse of the Slater matrix is cached, so that the ratio
+/// $\psi(R')/\psi(R)$ after moving a single electron, as well as the
+/// update of the inverse after accepting such a move, cost $O(N^2)$
+/// by the Sherman-Morrison formula.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SlaterDeterminant<T> {
+    orbitals: Vec<T>,
+    dimension: usize,
+    #[serde(skip)]
+    inverse: Option<Array2<f64>>,
+}
+
+impl<T> SlaterDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    pub fn new(orbitals: Vec<T>) -> Self {
+        Self {
+            orbitals,
+            dimension: 3,
+            inverse: None,
+        }
+    }
+
+    /// Set the number of spatial dimensions of the orbitals, three by default.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn orbitals(&self) -> &[T] {
+        &self.orbitals
+    }
+
+    /// Construct the Slater matrix $A_{ij} = \phi_j(\mathbf{r}_i)$.
+    pub fn slater_matrix(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        let nelec = self.orbitals.len();
+        check_input(
+            cfg.rows() == nelec,
+            "a Slater determinant needs one electron per orbital",
+        )?;
+        let mut matrix = Array2::<f64>::zeros((nelec, nelec));
+        for (i, pos) in cfg.outer_iter().enumerate() {
+            let pos = pos.to_owned();
+            for (j, orbital) in self.orbitals.iter().enumerate() {
+                matrix[[i, j]] = orbital.value(&pos)?;
+            }
+        }
+        Ok(matrix)
+    }
+
+    fn orbital_values(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        self.orbitals
+            .iter()
+            .map(|orbital| orbital.value(pos))
+            .collect::<Result<Vec<_>>>()
+            .map(Array1::from_vec)
+    }
+}
+
+impl<T> Function<f64> for SlaterDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        // the determinant of an empty matrix is unity
+        if self.orbitals.is_empty() {
+            return Ok(1.0);
+        }
+        Ok(self.slater_matrix(cfg)?.det()?)
+    }
+
+    fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+        if self.orbitals.is_empty() {
+            return Ok((0.0, 1.0));
+        }
+        let (sign, log_det) = self.slater_matrix(cfg)?.sln_det()?;
+        Ok((log_det, sign))
+    }
+}
+
+impl<T> Differentiate for SlaterDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(self.vgl(cfg)?.1)
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(self.vgl(cfg)?.2)
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        let (log_value, sign, grad, lapl) = self.log_vgl(cfg)?;
+        let value = sign * log_value.exp();
+        let grad_squared = (&grad * &grad).sum();
+        Ok((value, value * grad, value * (lapl + grad_squared)))
+    }
+
+    /// Computes all orbital values and derivatives once, and uses
+    /// $\nabla_i \ln|\psi| = \sum_j \nabla\phi_j(\mathbf{r}_i) A^{-1}_{ji}$ and
+    /// $\nabla^2 \ln|\psi| = \sum_{ij} \nabla^2\phi_j(\mathbf{r}_i) A^{-1}_{ji}
+    /// - |\nabla \ln|\psi||^2$.
+    fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+        if self.orbitals.is_empty() {
+            return Ok((0.0, 1.0, Array2::zeros(cfg.dim()), 0.0));
+        }
+        let nelec = self.orbitals.len();
+        let mut matrix = Array2::<f64>::zeros((nelec, nelec));
+        let mut orbital_grads = Array3::<f64>::zeros((nelec, nelec, cfg.len_of(Axis(1))));
+        let mut orbital_lapls = Array2::<f64>::zeros((nelec, nelec));
+        for (i, pos) in cfg.outer_iter().enumerate() {
+            let pos = pos.to_owned();
+            for (j, orbital) in self.orbitals.iter().enumerate() {
+                let (value, grad, lapl) = orbital.vgl(&pos)?;
+                matrix[[i, j]] = value;
+                orbital_grads.slice_mut(s![i, j, ..]).assign(&grad);
+                orbital_lapls[[i, j]] = lapl;
+            }
+        }
+        let (sign, log_value) = matrix.sln_det()?;
+        let inverse = matrix.inv()?;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        for (i, mut grad_i) in grad.outer_iter_mut().enumerate() {
+            for j in 0..nelec {
+                grad_i.scaled_add(inverse[[j, i]], &orbital_grads.slice(s![i, j, ..]));
+                lapl += orbital_lapls[[i, j]] * inverse[[j, i]];
+            }
+        }
+        let grad_squared = (&grad * &grad).sum();
+        Ok((log_value, sign, grad, lapl - grad_squared))
+    }
+}
+
+impl<T> WaveFunction for SlaterDeterminant<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    fn num_electrons(&self) -> usize {
+        self.orbitals.len()
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Ratio computed from the cached inverse in $O(N)$ operations.
+    /// Requires the cache to be initialized by `refresh`.
+    fn ratio(&self, _cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<f64> {
+        let inverse = self.inverse.as_ref().ok_or(EmptyCacheError)?;
+        let row = self.orbital_values(pos)?;
+        Ok(row.dot(&inverse.column(idx)))
+    }
+
+    /// $\nabla_i \psi(R')/\psi(R') = \sum_j \nabla\phi_j(\mathbf{r}') A'^{-1}_{ji}$,
+    /// where column $i$ of the updated inverse is that of the cached
+    /// inverse divided by the ratio.
+    fn gradient_ratio(
+        &self,
+        cfg: &Array2<f64>,
+        pos: &Array1<f64>,
+        idx: usize,
+    ) -> Result<Array1<f64>> {
+        let inverse = self.inverse.as_ref().ok_or(EmptyCacheError)?;
+        let ratio = self.ratio(cfg, pos, idx)?;
+        let mut grad = Array1::<f64>::zeros(pos.len());
+        for (orbital, b) in self.orbitals.iter().zip(inverse.column(idx).iter()) {
+            grad.scaled_add(*b, &orbital.gradient(pos)?);
+        }
+        Ok(grad / ratio)
+    }
+
+    /// Recompute the cached inverse Slater matrix from scratch.
+    fn refresh(&mut self, cfg: &Array2<f64>) -> Result<()> {
+        self.inverse = Some(if self.orbitals.is_empty() {
+            Array2::zeros((0, 0))
+        } else {
+            self.slater_matrix(cfg)?.inv()?
+        });
+        Ok(())
+    }
+
+    /// Update the cached inverse using the Sherman-Morrison formula.
+    fn accept_move(&mut self, _cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<()> {
+        let row = self.orbital_values(pos)?;
+        let inverse = self.inverse.as_mut().ok_or(EmptyCacheError)?;
+        let row_times_inverse = row.dot(inverse);
+        let ratio = row_times_inverse[idx];
+        let column = inverse.column(idx).to_owned();
+        for (k, mut inverse_column) in inverse.axis_iter_mut(Axis(1)).enumerate() {
+            if k == idx {
+                inverse_column /= ratio;
+            } else {
+                inverse_column.scaled_add(-row_times_inverse[k] / ratio, &column);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Product of a spin-up and a spin-down Slater determinant:
+/// $\psi = D_{\uparrow}(\mathbf{r}_1, \dots, \mathbf{r}_{N_{\uparrow}})
+/// D_{\downarrow}(\mathbf{r}_{N_{\uparrow} + 1}, \dots, \mathbf{r}_N)$.
+/// The first `num_up` electrons of a configuration are spin-up; single-electron
+/// moves are routed to the determinant of the corresponding spin.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpinDeterminantProduct<T> {
+    up: SlaterDeterminant<T>,
+    down: SlaterDeterminant<T>,
+}
+
+impl<T> SpinDeterminantProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    pub fn new(orbitals_up: Vec<T>, orbitals_down: Vec<T>) -> Self {
+        Self {
+            up: SlaterDeterminant::new(orbitals_up),
+            down: SlaterDeterminant::new(orbitals_down),
+        }
+    }
+
+    /// Set the number of spatial dimensions of the orbitals, three by default.
+    pub fn with_dimension(self, dimension: usize) -> Self {
+        Self {
+            up: self.up.with_dimension(dimension),
+            down: self.down.with_dimension(dimension),
+        }
+    }
+
+    pub fn up(&self) -> &SlaterDeterminant<T> {
+        &self.up
+    }
+
+    pub fn down(&self) -> &SlaterDeterminant<T> {
+        &self.down
+    }
+
+    fn split_config(&self, cfg: &Array2<f64>) -> (Array2<f64>, Array2<f64>) {
+        let nup = self.up.num_electrons();
+        (
+            cfg.slice(s![..nup, ..]).to_owned(),
+            cfg.slice(s![nup.., ..]).to_owned(),
+        )
+    }
+}
+
+impl<T> Function<f64> for SpinDeterminantProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        Ok(self.up.value(&cfg_up)? * self.down.value(&cfg_down)?)
+    }
+
+    fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        let (log_up, sign_up) = self.up.log_value(&cfg_up)?;
+        let (log_down, sign_down) = self.down.log_value(&cfg_down)?;
+        Ok((log_up + log_down, sign_up * sign_down))
+    }
+}
+
+impl<T> Differentiate for SpinDeterminantProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(self.vgl(cfg)?.1)
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(self.vgl(cfg)?.2)
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        let (log_value, sign, grad, lapl) = self.log_vgl(cfg)?;
+        let value = sign * log_value.exp();
+        let grad_squared = (&grad * &grad).sum();
+        Ok((value, value * grad, value * (lapl + grad_squared)))
+    }
+
+    fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+        let nup = self.up.num_electrons();
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        let (log_up, sign_up, grad_up, lapl_up) = self.up.log_vgl(&cfg_up)?;
+        let (log_down, sign_down, grad_down, lapl_down) = self.down.log_vgl(&cfg_down)?;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        grad.slice_mut(s![..nup, ..]).assign(&grad_up);
+        grad.slice_mut(s![nup.., ..]).assign(&grad_down);
+        Ok((
+            log_up + log_down,
+            sign_up * sign_down,
+            grad,
+            lapl_up + lapl_down,
+        ))
+    }
+}
+
+impl<T> WaveFunction for SpinDeterminantProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    fn num_electrons(&self) -> usize {
+        self.up.num_electrons() + self.down.num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.up.num_electrons()
+    }
+
+    fn dimension(&self) -> usize {
+        self.up.dimension()
+    }
+
+    /// Only the determinant of the moved electron's spin changes.
+    fn ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<f64> {
+        let nup = self.up.num_electrons();
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        if idx < nup {
+            self.up.ratio(&cfg_up, pos, idx)
+        } else {
+            self.down.ratio(&cfg_down, pos, idx - nup)
+        }
+    }
+
+    fn gradient_ratio(
+        &self,
+        cfg: &Array2<f64>,
+        pos: &Array1<f64>,
+        idx: usize,
+    ) -> Result<Array1<f64>> {
+        let nup = self.up.num_electrons();
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        if idx < nup {
+            self.up.gradient_ratio(&cfg_up, pos, idx)
+        } else {
+            self.down.gradient_ratio(&cfg_down, pos, idx - nup)
+        }
+    }
+
+    fn refresh(&mut self, cfg: &Array2<f64>) -> Result<()> {
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        self.up.refresh(&cfg_up)?;
+        self.down.refresh(&cfg_down)
+    }
+
+    fn accept_move(&mut self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<()> {
+        let nup = self.up.num_electrons();
+        let (cfg_up, cfg_down) = self.split_config(cfg);
+        if idx < nup {
+            self.up.accept_move(&cfg_up, pos, idx)
+        } else {
+            self.down.accept_move(&cfg_down, pos, idx - nup)
+        }
+    }
+}
+
+/// The orbitals are kept fixed, so that a Slater-Jastrow product is optimized
+/// through the parameters of its Jastrow factor.
+impl<T> Optimize for SpinDeterminantProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    fn parameter_gradient(&self, _cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        Ok(Array1::zeros(0))
+    }
+
+    fn log_parameter_gradient(&self, _cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        Ok(Array1::zeros(0))
+    }
+
+    fn update_parameters(&mut self, _deltap: &Array1<f64>) {}
+
+    fn parameters(&self) -> Array1<f64> {
+        Array1::zeros(0)
+    }
+
+    fn num_parameters(&self) -> usize {
+        0
+    }
+}
+
+impl<T: Validate> Validate for SlaterDeterminant<T> {
+    fn validate(&self) -> Result<()> {
+        self.orbitals.iter().try_for_each(Validate::validate)
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        Some(self.orbitals.len())
+    }
+}
+
+impl<T: Validate> Validate for SpinDeterminantProduct<T> {
+    fn validate(&self) -> Result<()> {
+        self.up.validate()?;
+        self.down.validate()
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        Some(self.up.orbitals.len() + self.down.orbitals.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array;
+    use ndarray_linalg::Norm;
+
+    // Gaussian orbital centered at an arbitrary point
+    #[derive(Clone)]
+    struct GaussianOrbital {
+        center: Array1<f64>,
+        exponent: f64,
+    }
+
+    impl Function<f64> for GaussianOrbital {
+        type D = Ix1;
+
+        fn value(&self, pos: &Array1<f64>) -> Result<f64> {
+            let r = pos - &self.center;
+            Ok(f64::exp(-self.exponent * r.norm_l2().powi(2)))
+        }
+    }
+
+    impl Differentiate for GaussianOrbital {
+        type D = Ix1;
+
+        fn gradient(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+            let r = pos - &self.center;
+            Ok(-2.0 * self.exponent * self.value(pos)? * r)
+        }
+
+        fn laplacian(&self, pos: &Array1<f64>) -> Result<f64> {
+            let r2 = (pos - &self.center).norm_l2().powi(2);
+            Ok(self.value(pos)? * (4.0 * self.exponent.powi(2) * r2 - 6.0 * self.exponent))
+        }
+    }
+
+    fn determinant() -> SlaterDeterminant<GaussianOrbital> {
+        let centers = array![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, -0.5, 0.5]];
+        SlaterDeterminant::new(
+            centers
+                .outer_iter()
+                .zip(&[0.8, 1.0, 1.2])
+                .map(|(center, &exponent)| GaussianOrbital {
+                    center: center.to_owned(),
+                    exponent,
+                })
+                .collect(),
+        )
+    }
+
+    fn configuration() -> Array2<f64> {
+        array![[0.1, 0.2, -0.3], [0.9, -0.1, 0.2], [-0.2, -0.4, 0.7]]
+    }
+
+    #[test]
+    fn antisymmetric_under_exchange() {
+        let det = determinant();
+        let cfg = configuration();
+        let mut swapped = cfg.clone();
+        swapped.row_mut(0).assign(&cfg.row(2));
+        swapped.row_mut(2).assign(&cfg.row(0));
+        let value = det.value(&cfg).unwrap();
+        assert!((value + det.value(&swapped).unwrap()).abs() < 1e-12 * value.abs());
+    }
+
+    #[test]
+    fn wrong_number_of_electrons() {
+        let det = determinant();
+        let cfg = array![
+            [0.1, 0.2, -0.3],
+            [0.9, -0.1, 0.2],
+            [-0.2, -0.4, 0.7],
+            [0.0, 0.0, 0.0]
+        ];
+        match det.value(&cfg) {
+            Err(Error::InputError(_)) => (),
+            _ => panic!("expected an input error"),
+        }
+    }
+
+    #[test]
+    fn sherman_morrison_matches_full_update() {
+        let mut det = determinant();
+        let cfg = configuration();
+        det.refresh(&cfg).unwrap();
+
+        let pos = array![0.3, 0.1, 0.0];
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(1).assign(&pos);
+
+        let ratio_exact = det.value(&cfg_new).unwrap() / det.value(&cfg).unwrap();
+        assert!((det.ratio(&cfg, &pos, 1).unwrap() - ratio_exact).abs() < 1e-10);
+
+        let grad_exact =
+            det.gradient(&cfg_new).unwrap().row(1).to_owned() / det.value(&cfg_new).unwrap();
+        let grad = det.gradient_ratio(&cfg, &pos, 1).unwrap();
+        assert!((grad - grad_exact).norm_l2() < 1e-10);
+
+        det.accept_move(&cfg, &pos, 1).unwrap();
+        let inverse_exact = det.slater_matrix(&cfg_new).unwrap().inv().unwrap();
+        let diff = det.inverse.as_ref().unwrap() - &inverse_exact;
+        assert!(diff.norm_l2() < 1e-10);
+    }
+
+    #[test]
+    fn spin_product_routes_moves() {
+        let orbitals = determinant().orbitals().to_vec();
+        let mut wf = SpinDeterminantProduct::new(orbitals[..2].to_vec(), orbitals[..1].to_vec());
+        let cfg = configuration();
+        wf.refresh(&cfg).unwrap();
+        assert_eq!(wf.num_up(), 2);
+        assert_eq!(wf.num_down(), 1);
+
+        for &idx in &[1, 2] {
+            let pos = array![0.3, 0.1, 0.0];
+            let mut cfg_new = cfg.clone();
+            cfg_new.row_mut(idx).assign(&pos);
+            let ratio_exact = wf.value(&cfg_new).unwrap() / wf.value(&cfg).unwrap();
+            assert!((wf.ratio(&cfg, &pos, idx).unwrap() - ratio_exact).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn log_value_survives_underflow() {
+        let det = determinant();
+        let cfg = configuration();
+        let (log_value, sign) = det.log_value(&cfg).unwrap();
+        let value = det.value(&cfg).unwrap();
+        assert!((log_value - value.abs().ln()).abs() < 1e-10);
+        assert_eq!(sign, value.signum());
+
+        // orbital values are of order exp(-400) here, so the determinant underflows
+        let far = array![[20.0, 0.0, 0.0], [0.0, 20.0, 0.0], [0.0, 0.0, 20.0]];
+        assert_eq!(det.value(&far).unwrap(), 0.0);
+        let (log_value, _) = det.log_value(&far).unwrap();
+        assert!(log_value.is_finite() && log_value < -1000.0);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        const H: f64 = 1e-4;
+        let det = determinant();
+        let cfg = configuration();
+        let grad = det.gradient(&cfg).unwrap();
+        let mut lapl = 0.0;
+        for i in 0..cfg.len_of(Axis(0)) {
+            for k in 0..cfg.len_of(Axis(1)) {
+                let mut step = Array::zeros(cfg.dim());
+                step[[i, k]] = H;
+                let forward = det.value(&(&cfg + &step)).unwrap();
+                let backward = det.value(&(&cfg - &step)).unwrap();
+                let central = det.value(&cfg).unwrap();
+                assert!((grad[[i, k]] - (forward - backward) / (2.0 * H)).abs() < 1e-6);
+                lapl += (forward - 2.0 * central + backward) / H.powi(2);
+            }
+        }
+        assert!((det.laplacian(&cfg).unwrap() - lapl).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,279 @@
+// Standard imports
+use std::f64::consts::PI;
+// Third party imports
+use ndarray::{Array1, Array2, Axis, Ix1};
+use ndarray_linalg::Norm;
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::determinant::SpinDeterminantProduct;
+use crate::jastrow::RpaJastrow;
+use errors::Error;
+use operator::{KineticEnergy, OperatorSum, PeriodicElectronicPotential};
+use wavefunction_traits::{
+    check_input, Differentiate, Function, Hessian, Product, SimulationCell, Validate,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Real plane wave $\cos(\mathbf{k}\cdot\mathbf{r})$ or $\sin(\mathbf{k}\cdot\mathbf{r})$.
+/// For closed shells, where both $\pm\mathbf{k}$ are occupied, these span
+/// the same space as the complex plane waves $e^{\pm i\mathbf{k}\cdot\mathbf{r}}$.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PlaneWaveOrbital {
+    wave_vector: Array1<f64>,
+    sine: bool,
+}
+
+impl PlaneWaveOrbital {
+    pub fn cosine(wave_vector: Array1<f64>) -> Self {
+        Self {
+            wave_vector,
+            sine: false,
+        }
+    }
+
+    pub fn sine(wave_vector: Array1<f64>) -> Self {
+        Self {
+            wave_vector,
+            sine: true,
+        }
+    }
+
+    pub fn wave_vector(&self) -> &Array1<f64> {
+        &self.wave_vector
+    }
+
+    /// The orbital and its derivative with respect to the phase $\mathbf{k}\cdot\mathbf{r}$.
+    fn phase_derivatives(&self, pos: &Array1<f64>) -> (f64, f64) {
+        let phase = self.wave_vector.dot(pos);
+        if self.sine {
+            (phase.sin(), phase.cos())
+        } else {
+            (phase.cos(), -phase.sin())
+        }
+    }
+}
+
+impl Function<f64> for PlaneWaveOrbital {
+    type D = Ix1;
+
+    fn value(&self, pos: &Array1<f64>) -> Result<f64> {
+        Ok(self.phase_derivatives(pos).0)
+    }
+}
+
+impl Differentiate for PlaneWaveOrbital {
+    type D = Ix1;
+
+    fn gradient(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        Ok(&self.wave_vector * self.phase_derivatives(pos).1)
+    }
+
+    fn laplacian(&self, pos: &Array1<f64>) -> Result<f64> {
+        Ok(-self.wave_vector.dot(&self.wave_vector) * self.value(pos)?)
+    }
+}
+
+impl Hessian for PlaneWaveOrbital {
+    fn hessian(&self, pos: &Array1<f64>) -> Result<Array2<f64>> {
+        let k = self.wave_vector.view().insert_axis(Axis(1));
+        Ok(-k.dot(&k.t()) * self.value(pos)?)
+    }
+}
+
+/// The homogeneous electron gas: `num_up` spin-up and `num_down` spin-down
+/// electrons in a cubic cell, neutralized by a uniform positive background.
+/// The density is set by $r_s$, the radius of the sphere that holds one
+/// electron on average. Each spin species occupies the lowest plane waves,
+/// which must form closed shells, i.e. 1, 7, 19, 27, 33, 57, ... electrons.
+#[derive(Clone)]
+pub struct HomogeneousElectronGas {
+    num_up: usize,
+    num_down: usize,
+    density_parameter: f64,
+    cell: SimulationCell,
+    potential: PeriodicElectronicPotential,
+}
+
+impl HomogeneousElectronGas {
+    pub fn new(num_up: usize, num_down: usize, density_parameter: f64) -> Result<Self> {
+        let num_electrons = (num_up + num_down) as f64;
+        let side = (4.0 * PI * num_electrons / 3.0).cbrt() * density_parameter;
+        let cell = SimulationCell::cubic(side)?;
+        let heg = Self {
+            num_up,
+            num_down,
+            density_parameter,
+            potential: PeriodicElectronicPotential::new(cell.clone())?,
+            cell,
+        };
+        // fail early on open shells
+        heg.wave_vectors(num_up.max(num_down))?;
+        Ok(heg)
+    }
+
+    pub fn num_up(&self) -> usize {
+        self.num_up
+    }
+
+    pub fn num_down(&self) -> usize {
+        self.num_down
+    }
+
+    pub fn density_parameter(&self) -> f64 {
+        self.density_parameter
+    }
+
+    pub fn cell(&self) -> &SimulationCell {
+        &self.cell
+    }
+
+    /// Wave vectors of the `n` lowest plane waves that fit in the cell.
+    /// Fails if they do not fill a closed shell.
+    pub fn wave_vectors(&self, n: usize) -> Result<Vec<Array1<f64>>> {
+        let mut cutoff = self.cell.reciprocal()[[0, 0]];
+        let mut points = self.cell.reciprocal_points(cutoff);
+        while points.len() <= n {
+            cutoff *= 2.0;
+            points = self.cell.reciprocal_points(cutoff);
+        }
+        if n > 0 {
+            check_input(
+                points[n].norm_l2() - points[n - 1].norm_l2() > 1e-8 * cutoff,
+                &format!("{} plane waves do not fill a closed shell", n),
+            )?;
+        }
+        points.truncate(n);
+        Ok(points)
+    }
+
+    /// Real orbitals spanning the `n` lowest plane waves.
+    pub fn orbitals(&self, n: usize) -> Result<Vec<PlaneWaveOrbital>> {
+        let mut orbitals = Vec::with_capacity(n);
+        let mut pairs: Vec<Array1<f64>> = Vec::new();
+        for k in self.wave_vectors(n)? {
+            if k.norm_l2() == 0.0 {
+                orbitals.push(PlaneWaveOrbital::cosine(k));
+            } else if pairs.iter().all(|q| (q + &k).norm_l2() > 1e-8) {
+                pairs.push(k.clone());
+                orbitals.push(PlaneWaveOrbital::cosine(k.clone()));
+                orbitals.push(PlaneWaveOrbital::sine(k));
+            }
+        }
+        Ok(orbitals)
+    }
+
+    pub fn slater_determinant(&self) -> Result<SpinDeterminantProduct<PlaneWaveOrbital>> {
+        Ok(SpinDeterminantProduct::new(
+            self.orbitals(self.num_up)?,
+            self.orbitals(self.num_down)?,
+        ))
+    }
+
+    /// RPA Jastrow factor with $A = 1/\omega_p = \sqrt{r_s^3/3}$.
+    pub fn rpa_jastrow(&self) -> RpaJastrow {
+        let a = (self.density_parameter.powi(3) / 3.0).sqrt();
+        RpaJastrow::new(self.cell.clone(), self.num_up, a)
+    }
+
+    /// Slater-Jastrow wave function of plane waves and the RPA Jastrow factor.
+    pub fn wave_function(
+        &self,
+    ) -> Result<Product<SpinDeterminantProduct<PlaneWaveOrbital>, RpaJastrow>> {
+        Ok(Product::new(self.slater_determinant()?, self.rpa_jastrow()))
+    }
+
+    /// Hamiltonian of the electrons and the background, with the Coulomb
+    /// interaction summed by the Ewald method.
+    pub fn hamiltonian(&self) -> OperatorSum<KineticEnergy, PeriodicElectronicPotential> {
+        OperatorSum::new(KineticEnergy::new(), self.potential.clone())
+    }
+
+    /// Expectation value of the Hamiltonian in the Slater determinant, i.e.
+    /// the Hartree-Fock energy of the finite system. The Hartree energy
+    /// cancels against the background, leaving the kinetic energy, the
+    /// exchange energy $-\frac{2\pi}{V}\sum_\sigma \sum_{\mathbf{k} \neq
+    /// \mathbf{k}'} |\mathbf{k} - \mathbf{k}'|^{-2}$, and the Madelung energy
+    /// of each electron with its own images.
+    pub fn hartree_fock_energy(&self) -> Result<f64> {
+        let madelung = self
+            .potential
+            .ewald()
+            .energy(&Array2::zeros((1, 3)), &array![1.0]);
+        let mut energy = (self.num_up + self.num_down) as f64 * madelung;
+        for &n in &[self.num_up, self.num_down] {
+            let wave_vectors = self.wave_vectors(n)?;
+            for (a, ka) in wave_vectors.iter().enumerate() {
+                energy += 0.5 * ka.dot(ka);
+                for kb in wave_vectors.iter().skip(a + 1) {
+                    let q = ka - kb;
+                    energy -= 4.0 * PI / (self.cell.volume() * q.dot(&q));
+                }
+            }
+        }
+        Ok(energy)
+    }
+}
+
+/// Energy per electron of the unpolarized electron gas in the thermodynamic
+/// limit, from the DMC calculations of Ceperley and Alder, as the exact
+/// kinetic and exchange energies plus the correlation energy parametrized by
+/// Perdew and Zunger, Phys. Rev. B 23, 5048 (1981).
+pub fn ceperley_alder_energy(density_parameter: f64) -> f64 {
+    let rs = density_parameter;
+    let fermi_wave_vector = (9.0 * PI / 4.0).cbrt() / rs;
+    let kinetic = 0.3 * fermi_wave_vector.powi(2);
+    let exchange = -0.75 * fermi_wave_vector / PI;
+    let correlation = if rs >= 1.0 {
+        -0.1423 / (1.0 + 1.0529 * rs.sqrt() + 0.3334 * rs)
+    } else {
+        0.0311 * rs.ln() - 0.048 + 0.0020 * rs * rs.ln() - 0.0116 * rs
+    };
+    kinetic + exchange + correlation
+}
+
+impl Validate for PlaneWaveOrbital {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wavefunction_traits::WaveFunction;
+
+    #[test]
+    fn closed_shells() {
+        let heg = HomogeneousElectronGas::new(7, 7, 2.0).unwrap();
+        assert_eq!(heg.orbitals(7).unwrap().len(), 7);
+        assert_eq!(heg.orbitals(19).unwrap().len(), 19);
+        let wf = heg.slater_determinant().unwrap();
+        assert_eq!((wf.num_up(), wf.num_down()), (7, 7));
+
+        // the determinant is periodic in the cell
+        let side = heg.cell().lattice()[[0, 0]];
+        let cfg = Array2::from_shape_fn((14, 3), |(i, k)| side * ((5 * i + 3 * k) as f64).sin());
+        let mut shifted = cfg.clone();
+        shifted[[3, 1]] += side;
+        shifted[[10, 2]] -= 2.0 * side;
+        let (value, shifted_value) = (wf.value(&cfg).unwrap(), wf.value(&shifted).unwrap());
+        assert!((value - shifted_value).abs() < 1e-10 * value.abs());
+    }
+
+    #[test]
+    fn open_shells_are_rejected() {
+        match HomogeneousElectronGas::new(5, 5, 1.0) {
+            Err(Error::InputError(message)) => assert!(message.contains("closed shell")),
+            _ => panic!("expected an input error"),
+        }
+        let heg = HomogeneousElectronGas::new(7, 7, 1.0).unwrap();
+        assert!(heg.orbitals(8).is_err());
+    }
+
+    #[test]
+    fn ceperley_alder_reference() {
+        // the Perdew-Zunger fit is continuous at $r_s = 1$
+        let low = ceperley_alder_energy(1.0 - 1e-9);
+        let high = ceperley_alder_energy(1.0);
+        assert!((low - high).abs() < 1e-4);
+        // the energy at $r_s = 1$ is $1.105 - 0.458 - 0.060$ Hartree
+        assert!((high - 0.5869).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,864 @@
+// Third party imports
+use ndarray::{Array1, Array2, Axis, Ix2};
+use ndarray_linalg::Norm;
+use serde::{Deserialize, Serialize};
+// First party imports
+use errors::Error;
+use wavefunction_traits::{
+    check_input, check_invariant, Differentiate, Function, LogVgl, Optimize, SimulationCell,
+    Validate, Vgl, WaveFunction,
+};
+
+type Result<T> = std::result::Result<T, Error>;
+
+const PADE_PARAMETERS: &str = "a Pade-polynomial Jastrow factor needs at least two parameters";
+
+/// Scaled distance $R(r) = (1 - e^{-\kappa r})/\kappa$, along with its
+/// first and second derivatives with respect to $r$.
+fn scaled_distance(r: f64, kappa: f64) -> (f64, f64, f64) {
+    let exp = f64::exp(-kappa * r);
+    ((1.0 - exp) / kappa, exp, -kappa * exp)
+}
+
+/// Pade-polynomial $u(R) = \frac{b_1 R}{1 + b_2 R} + \sum_{p=2}^{N_b - 1} b_{p+1} R^p$,
+/// along with its first and second derivatives with respect to $R$.
+fn pade_polynomial(b: &Array1<f64>, rs: f64) -> (f64, f64, f64) {
+    let denom = 1.0 + b[1] * rs;
+    let mut u = b[0] * rs / denom;
+    let mut du = b[0] / denom.powi(2);
+    let mut d2u = -2.0 * b[0] * b[1] / denom.powi(3);
+    for (p, bp) in b.iter().enumerate().skip(2) {
+        let p = p as i32;
+        u += bp * rs.powi(p);
+        du += f64::from(p) * bp * rs.powi(p - 1);
+        d2u += f64::from(p * (p - 1)) * bp * rs.powi(p - 2);
+    }
+    (u, du, d2u)
+}
+
+/// Derivatives of the Pade-polynomial $u(R)$ with respect to its parameters.
+fn pade_polynomial_parameter_gradient(b: &Array1<f64>, rs: f64) -> Array1<f64> {
+    let denom = 1.0 + b[1] * rs;
+    let mut grad = Array1::<f64>::zeros(b.len());
+    grad[0] = rs / denom;
+    grad[1] = -b[0] * rs.powi(2) / denom.powi(2);
+    for p in 2..b.len() {
+        grad[p] = rs.powi(p as i32);
+    }
+    grad
+}
+
+/// Call `f` with the indices, distance $r_{ij}$ and separation
+/// $\mathbf{r}_i - \mathbf{r}_j$ of each pair of particles $i < j$.
+fn for_each_pair<F>(cfg: &Array2<f64>, mut f: F)
+where
+    F: FnMut(usize, usize, f64, &Array1<f64>),
+{
+    let nelec = cfg.len_of(Axis(0));
+    for i in 0..nelec {
+        for j in i + 1..nelec {
+            let rij = &cfg.row(i) - &cfg.row(j);
+            f(i, j, rij.norm_l2(), &rij);
+        }
+    }
+}
+
+/// Electron-electron Jastrow factor $J = \exp(f_{ee})$, with
+/// $f_{ee} = \sum_{i<j} \left( \frac{b_1 R_{ij}}{1 + b_2 R_{ij}} +
+/// \sum_{p=2}^{N_b - 1} b_{p+1} R_{ij}^p \right)$
+/// in terms of the scaled distances $R_{ij} = (1 - e^{-\kappa r_{ij}})/\kappa$.
+/// See theory/jastrow.tex for the derivation of the derivatives.
+/// The variational parameters are $b_1, \dots, b_{N_b}$; $\kappa$ is fixed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Jastrow {
+    parameters: Array1<f64>,
+    kappa: f64,
+}
+
+impl Jastrow {
+    pub fn new(parameters: Array1<f64>, kappa: f64) -> Result<Self> {
+        check_input(parameters.len() >= 2, PADE_PARAMETERS)?;
+        Ok(Self { parameters, kappa })
+    }
+
+    /// The exponent $f_{ee}$ of the Jastrow factor.
+    pub fn exponent(&self, cfg: &Array2<f64>) -> f64 {
+        let mut f = 0.0;
+        for_each_pair(cfg, |_, _, r, _| {
+            let (rs, _, _) = scaled_distance(r, self.kappa);
+            f += pade_polynomial(&self.parameters, rs).0;
+        });
+        f
+    }
+
+    /// The gradient $\nabla f_{ee}$ and laplacian $\nabla^2 f_{ee}$
+    /// of the Jastrow exponent.
+    pub fn exponent_derivatives(&self, cfg: &Array2<f64>) -> (Array2<f64>, f64) {
+        let dim = cfg.len_of(Axis(1)) as f64;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        for_each_pair(cfg, |i, j, r, rij| {
+            let (rs, drs, d2rs) = scaled_distance(r, self.kappa);
+            let (_, du, d2u) = pade_polynomial(&self.parameters, rs);
+            // derivatives of u(R(r)) with respect to r
+            let df = du * drs;
+            let d2f = d2u * drs.powi(2) + du * d2rs;
+            let unit = rij / r;
+            grad.row_mut(i).scaled_add(df, &unit);
+            grad.row_mut(j).scaled_add(-df, &unit);
+            // both electrons of the pair contribute equally
+            lapl += 2.0 * (d2f + (dim - 1.0) * df / r);
+        });
+        (grad, lapl)
+    }
+
+    /// Derivatives of the Jastrow exponent $f_{ee}$ with respect to the parameters.
+    pub fn exponent_parameter_gradient(&self, cfg: &Array2<f64>) -> Array1<f64> {
+        let mut grad = Array1::<f64>::zeros(self.parameters.len());
+        for_each_pair(cfg, |_, _, r, _| {
+            let (rs, _, _) = scaled_distance(r, self.kappa);
+            grad += &pade_polynomial_parameter_gradient(&self.parameters, rs);
+        });
+        grad
+    }
+
+    /// Names $b_1, \dots, b_{N_b}$ of the parameters.
+    fn names(&self) -> Vec<String> {
+        (1..=self.parameters.len())
+            .map(|p| format!("b_{}", p))
+            .collect()
+    }
+}
+
+/// Implement `Function`, `Differentiate` and `Optimize` for a Jastrow factor
+/// $J = \exp(f)$ in terms of the derivatives of its exponent $f$.
+macro_rules! impl_jastrow_factor {
+    ($t:ty, $group:expr) => {
+        impl Function<f64> for $t {
+            type D = Ix2;
+
+            fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+                Ok(f64::exp(self.exponent(cfg)))
+            }
+
+            fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+                Ok((self.exponent(cfg), 1.0))
+            }
+        }
+
+        impl Differentiate for $t {
+            type D = Ix2;
+
+            fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+                let (grad, _) = self.exponent_derivatives(cfg);
+                Ok(self.value(cfg)? * grad)
+            }
+
+            fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+                // $\nabla^2 J = J(\nabla^2 f + |\nabla f|^2)$
+                let (grad, lapl) = self.exponent_derivatives(cfg);
+                Ok(self.value(cfg)? * (lapl + grad.norm_l2().powi(2)))
+            }
+
+            fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+                let value = self.value(cfg)?;
+                let (grad, lapl) = self.exponent_derivatives(cfg);
+                let lapl = value * (lapl + grad.norm_l2().powi(2));
+                Ok((value, value * grad, lapl))
+            }
+
+            fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+                let (grad, lapl) = self.exponent_derivatives(cfg);
+                Ok((self.exponent(cfg), 1.0, grad, lapl))
+            }
+        }
+
+        impl Optimize for $t {
+            fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+                Ok(self.value(cfg)? * self.exponent_parameter_gradient(cfg))
+            }
+
+            fn log_parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+                Ok(self.exponent_parameter_gradient(cfg))
+            }
+
+            fn update_parameters(&mut self, deltap: &Array1<f64>) {
+                self.parameters += deltap;
+            }
+
+            fn parameters(&self) -> Array1<f64> {
+                self.parameters.clone()
+            }
+
+            fn num_parameters(&self) -> usize {
+                self.parameters.len()
+            }
+
+            fn parameter_names(&self) -> Vec<String> {
+                self.names()
+            }
+
+            fn parameter_groups(&self) -> Vec<String> {
+                vec![String::from($group); self.parameters.len()]
+            }
+        }
+    };
+}
+
+impl_jastrow_factor!(Jastrow, "electron-electron Jastrow");
+
+/// One-body electron-nucleus Jastrow factor $J = \exp(f_{en})$, with
+/// $f_{en} = \sum_{i=1}^{N_e} \sum_{I=1}^{N_{\mathrm{ions}}} \left(
+/// \frac{a_1 R_{iI}}{1 + a_2 R_{iI}} + \sum_{p=2}^{N_a - 1} a_{p+1} R_{iI}^p \right)$,
+/// where $R_{iI}$ is the scaled distance between electron $i$ and ion $I$.
+/// Ion positions are given as rows of an array, as for `IonicPotential`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ElectronNucleusJastrow {
+    ion_positions: Array2<f64>,
+    parameters: Array1<f64>,
+    kappa: f64,
+}
+
+impl ElectronNucleusJastrow {
+    pub fn new(ion_positions: Array2<f64>, parameters: Array1<f64>, kappa: f64) -> Result<Self> {
+        check_input(parameters.len() >= 2, PADE_PARAMETERS)?;
+        Ok(Self {
+            ion_positions,
+            parameters,
+            kappa,
+        })
+    }
+
+    /// The exponent $f_{en}$ of the Jastrow factor.
+    pub fn exponent(&self, cfg: &Array2<f64>) -> f64 {
+        let mut f = 0.0;
+        self.for_each_electron_ion(cfg, |_, r, _| {
+            let (rs, _, _) = scaled_distance(r, self.kappa);
+            f += pade_polynomial(&self.parameters, rs).0;
+        });
+        f
+    }
+
+    /// The gradient $\nabla f_{en}$ and laplacian $\nabla^2 f_{en}$
+    /// of the Jastrow exponent.
+    pub fn exponent_derivatives(&self, cfg: &Array2<f64>) -> (Array2<f64>, f64) {
+        let dim = cfg.len_of(Axis(1)) as f64;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        self.for_each_electron_ion(cfg, |i, r, r_ei| {
+            let (rs, drs, d2rs) = scaled_distance(r, self.kappa);
+            let (_, du, d2u) = pade_polynomial(&self.parameters, rs);
+            let df = du * drs;
+            let d2f = d2u * drs.powi(2) + du * d2rs;
+            grad.row_mut(i).scaled_add(df / r, r_ei);
+            lapl += d2f + (dim - 1.0) * df / r;
+        });
+        (grad, lapl)
+    }
+
+    /// Derivatives of the Jastrow exponent $f_{en}$ with respect to the parameters.
+    pub fn exponent_parameter_gradient(&self, cfg: &Array2<f64>) -> Array1<f64> {
+        let mut grad = Array1::<f64>::zeros(self.parameters.len());
+        self.for_each_electron_ion(cfg, |_, r, _| {
+            let (rs, _, _) = scaled_distance(r, self.kappa);
+            grad += &pade_polynomial_parameter_gradient(&self.parameters, rs);
+        });
+        grad
+    }
+
+    /// Names $a_1, \dots, a_{N_a}$ of the parameters.
+    fn names(&self) -> Vec<String> {
+        (1..=self.parameters.len())
+            .map(|p| format!("a_{}", p))
+            .collect()
+    }
+
+    fn for_each_electron_ion<F>(&self, cfg: &Array2<f64>, mut f: F)
+    where
+        F: FnMut(usize, f64, &Array1<f64>),
+    {
+        for (i, electron) in cfg.outer_iter().enumerate() {
+            for ion in self.ion_positions.outer_iter() {
+                let r_ei = &electron - &ion;
+                f(i, r_ei.norm_l2(), &r_ei);
+            }
+        }
+    }
+}
+
+impl_jastrow_factor!(ElectronNucleusJastrow, "electron-nucleus Jastrow");
+
+/// Powers $x^n$, along with the first and second derivatives with respect to $x$.
+pub(crate) fn monomial(x: f64, n: i32) -> (f64, f64, f64) {
+    match n {
+        0 => (1.0, 0.0, 0.0),
+        1 => (x, 1.0, 0.0),
+        _ => (
+            x.powi(n),
+            f64::from(n) * x.powi(n - 1),
+            f64::from(n * (n - 1)) * x.powi(n - 2),
+        ),
+    }
+}
+
+/// Three-body electron-electron-nucleus Jastrow factor $J = \exp(f_{een})$, with
+/// $f_{een} = \sum_{I} \sum_{i<j} \sum_{klm} c_{klm} R_{ij}^k
+/// (R_{iI}^l R_{jI}^m + R_{iI}^m R_{jI}^l)$.
+/// The sum runs over all terms with $k \geq 1$, $l \geq 1$, $l \geq m \geq 0$ and
+/// $k + l + m \leq$ `order`, so that purely two-body terms are excluded.
+/// All parameters $c_{klm}$ are initialized to zero.
+/// Ion positions are given as rows of an array, as for `IonicPotential`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ElectronElectronNucleusJastrow {
+    ion_positions: Array2<f64>,
+    terms: Vec<(i32, i32, i32)>,
+    parameters: Array1<f64>,
+    kappa: f64,
+}
+
+impl ElectronElectronNucleusJastrow {
+    pub fn new(ion_positions: Array2<f64>, order: i32, kappa: f64) -> Self {
+        let mut terms = Vec::new();
+        for k in 1..=order {
+            for l in 1..=order - k {
+                for m in 0..=i32::min(l, order - k - l) {
+                    terms.push((k, l, m));
+                }
+            }
+        }
+        let parameters = Array1::zeros(terms.len());
+        Self {
+            ion_positions,
+            terms,
+            parameters,
+            kappa,
+        }
+    }
+
+    /// The exponents $(k, l, m)$ of each term in the expansion, in the
+    /// same order as the parameters.
+    pub fn terms(&self) -> &[(i32, i32, i32)] {
+        &self.terms
+    }
+
+    /// The exponent $f_{een}$ of the Jastrow factor.
+    pub fn exponent(&self, cfg: &Array2<f64>) -> f64 {
+        self.exponent_parameter_gradient(cfg).dot(&self.parameters)
+    }
+
+    /// The gradient $\nabla f_{een}$ and laplacian $\nabla^2 f_{een}$
+    /// of the Jastrow exponent.
+    pub fn exponent_derivatives(&self, cfg: &Array2<f64>) -> (Array2<f64>, f64) {
+        let dim = cfg.len_of(Axis(1)) as f64;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        self.for_each_triplet(cfg, |i, j, ee, ei, ej| {
+            let (a, da, d2a, rij) = ee;
+            let (b, db, d2b, r_ei) = ei;
+            let (c, dc, d2c, r_ej) = ej;
+            // laplacians of the scaled distances
+            let lapl_a = d2a + (dim - 1.0) * da / rij.norm_l2();
+            let lapl_b = d2b + (dim - 1.0) * db / r_ei.norm_l2();
+            let lapl_c = d2c + (dim - 1.0) * dc / r_ej.norm_l2();
+            // gradients of the scaled distances
+            let grad_a = rij * (da / rij.norm_l2());
+            let grad_b = r_ei * (db / r_ei.norm_l2());
+            let grad_c = r_ej * (dc / r_ej.norm_l2());
+            for (&(k, l, m), cp) in self.terms.iter().zip(self.parameters.iter()) {
+                let (ak, dak, d2ak) = monomial(a, k);
+                let (bl, dbl, d2bl) = monomial(b, l);
+                let (bm, dbm, d2bm) = monomial(b, m);
+                let (cl, dcl, d2cl) = monomial(c, l);
+                let (cm, dcm, d2cm) = monomial(c, m);
+                let poly = bl * cm + bm * cl;
+                let dpoly_b = dbl * cm + dbm * cl;
+                let dpoly_c = bl * dcm + bm * dcl;
+                let d2poly_b = d2bl * cm + d2bm * cl;
+                let d2poly_c = bl * d2cm + bm * d2cl;
+
+                let mut grad_i = grad.row_mut(i);
+                grad_i.scaled_add(cp * dak * poly, &grad_a);
+                grad_i.scaled_add(cp * ak * dpoly_b, &grad_b);
+                let mut grad_j = grad.row_mut(j);
+                grad_j.scaled_add(-cp * dak * poly, &grad_a);
+                grad_j.scaled_add(cp * ak * dpoly_c, &grad_c);
+
+                lapl += cp
+                    * (2.0 * (d2ak * da.powi(2) + dak * lapl_a) * poly
+                        + ak * (d2poly_b * db.powi(2) + dpoly_b * lapl_b)
+                        + ak * (d2poly_c * dc.powi(2) + dpoly_c * lapl_c)
+                        + 2.0 * dak * dpoly_b * grad_a.dot(&grad_b)
+                        - 2.0 * dak * dpoly_c * grad_a.dot(&grad_c));
+            }
+        });
+        (grad, lapl)
+    }
+
+    /// Derivatives of the Jastrow exponent $f_{een}$ with respect to the parameters.
+    pub fn exponent_parameter_gradient(&self, cfg: &Array2<f64>) -> Array1<f64> {
+        let mut grad = Array1::<f64>::zeros(self.parameters.len());
+        self.for_each_triplet(cfg, |_, _, (a, _, _, _), (b, _, _, _), (c, _, _, _)| {
+            for (&(k, l, m), g) in self.terms.iter().zip(grad.iter_mut()) {
+                *g += a.powi(k) * (b.powi(l) * c.powi(m) + b.powi(m) * c.powi(l));
+            }
+        });
+        grad
+    }
+
+    /// Names $c_{klm}$ of the parameters, written as `c_k,l,m`.
+    fn names(&self) -> Vec<String> {
+        self.terms
+            .iter()
+            .map(|(k, l, m)| format!("c_{},{},{}", k, l, m))
+            .collect()
+    }
+
+    /// Loop over all ions and electron pairs, passing the scaled distances
+    /// $R_{ij}$, $R_{iI}$ and $R_{jI}$ with their first and second derivatives,
+    /// and the corresponding separation vectors.
+    fn for_each_triplet<F>(&self, cfg: &Array2<f64>, mut f: F)
+    where
+        F: FnMut(
+            usize,
+            usize,
+            (f64, f64, f64, &Array1<f64>),
+            (f64, f64, f64, &Array1<f64>),
+            (f64, f64, f64, &Array1<f64>),
+        ),
+    {
+        let nelec = cfg.len_of(Axis(0));
+        for ion in self.ion_positions.outer_iter() {
+            let separations: Vec<_> = cfg
+                .outer_iter()
+                .map(|electron| {
+                    let r_ei = &electron - &ion;
+                    let (rs, drs, d2rs) = scaled_distance(r_ei.norm_l2(), self.kappa);
+                    (rs, drs, d2rs, r_ei)
+                })
+                .collect();
+            for i in 0..nelec {
+                for j in i + 1..nelec {
+                    let rij = &cfg.row(i) - &cfg.row(j);
+                    let (rs, drs, d2rs) = scaled_distance(rij.norm_l2(), self.kappa);
+                    let (b, db, d2b, ref r_ei) = separations[i];
+                    let (c, dc, d2c, ref r_ej) = separations[j];
+                    f(
+                        i,
+                        j,
+                        (rs, drs, d2rs, &rij),
+                        (b, db, d2b, r_ei),
+                        (c, dc, d2c, r_ej),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl_jastrow_factor!(
+    ElectronElectronNucleusJastrow,
+    "electron-electron-nucleus Jastrow"
+);
+
+/// McMillan pair Jastrow factor $J = \exp(f)$, with
+/// $f = -\frac{1}{2} \sum_{i<j} (b / r_{ij})^5$, which keeps particles with a
+/// hard-core repulsion, such as helium-4 atoms, apart. It is symmetric in the
+/// `num_particles` bosons, but not normalizable by itself; multiply it by a
+/// trap or long-range pair factor to bind the cluster.
+/// The variational parameter is $b$.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct McMillanJastrow {
+    num_particles: usize,
+    dimension: usize,
+    parameters: Array1<f64>,
+}
+
+impl McMillanJastrow {
+    pub fn new(num_particles: usize, b: f64) -> Self {
+        Self {
+            num_particles,
+            dimension: 3,
+            parameters: array![b],
+        }
+    }
+
+    /// Set the number of spatial dimensions, three by default.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    /// The exponent $f$ of the Jastrow factor.
+    pub fn exponent(&self, cfg: &Array2<f64>) -> f64 {
+        let b = self.parameters[0];
+        let mut f = 0.0;
+        for_each_pair(cfg, |_, _, r, _| f -= 0.5 * (b / r).powi(5));
+        f
+    }
+
+    /// The gradient $\nabla f$ and laplacian $\nabla^2 f$ of the Jastrow exponent.
+    pub fn exponent_derivatives(&self, cfg: &Array2<f64>) -> (Array2<f64>, f64) {
+        let b5 = self.parameters[0].powi(5);
+        let dim = cfg.len_of(Axis(1)) as f64;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        for_each_pair(cfg, |i, j, r, rij| {
+            let df = 2.5 * b5 / r.powi(6);
+            let d2f = -15.0 * b5 / r.powi(7);
+            let unit = rij / r;
+            grad.row_mut(i).scaled_add(df, &unit);
+            grad.row_mut(j).scaled_add(-df, &unit);
+            lapl += 2.0 * (d2f + (dim - 1.0) * df / r);
+        });
+        (grad, lapl)
+    }
+
+    /// Derivative of the Jastrow exponent with respect to $b$.
+    pub fn exponent_parameter_gradient(&self, cfg: &Array2<f64>) -> Array1<f64> {
+        let b = self.parameters[0];
+        let mut grad = 0.0;
+        for_each_pair(cfg, |_, _, r, _| grad -= 2.5 * b.powi(4) / r.powi(5));
+        array![grad]
+    }
+
+    fn names(&self) -> Vec<String> {
+        vec![String::from("b")]
+    }
+}
+
+impl_jastrow_factor!(McMillanJastrow, "McMillan Jastrow");
+
+impl WaveFunction for McMillanJastrow {
+    fn num_electrons(&self) -> usize {
+        self.num_particles
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// Gaskell RPA pair function $u(r) = \frac{A}{r}(1 - e^{-r/F})$, along with its
+/// first and second derivatives with respect to $r$, and its derivative with
+/// respect to $A$ at fixed $F^2/A$.
+fn rpa_pair_function(r: f64, a: f64, f: f64) -> (f64, f64, f64, f64) {
+    let exp = f64::exp(-r / f);
+    let g = 1.0 - exp;
+    let dg = exp / f;
+    let d2g = -exp / f.powi(2);
+    (
+        a * g / r,
+        a * (dg / r - g / r.powi(2)),
+        a * (d2g / r - 2.0 * dg / r.powi(2) + 2.0 * g / r.powi(3)),
+        g / r - exp / (2.0 * f),
+    )
+}
+
+/// RPA Jastrow factor $J = \exp(f)$ for the electron gas in a periodic
+/// `SimulationCell`, with $f = -\sum_{i<j} u_{\sigma_i\sigma_j}(r_{ij})$ and
+/// $u_{\sigma\sigma'}(r) = \frac{A}{r}(1 - e^{-r/F_{\sigma\sigma'}})$.
+/// The cusp conditions fix $F_{\uparrow\uparrow} = \sqrt{2A}$ and
+/// $F_{\uparrow\downarrow} = \sqrt{A}$, and the random phase approximation gives
+/// $A = 1/\omega_p = \sqrt{r_s^3/3}$. Following Ceperley, $u(r)$ is replaced by
+/// $u(r) + u(2L - r) - 2u(L)$ for minimum-image distances $r < L$, where $L$ is
+/// the radius of the sphere inscribed in the Wigner-Seitz cell, and by zero
+/// beyond, so that
+/// $J$ is periodic with a continuous gradient.
+/// The variational parameter is $A$.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "RpaJastrowParameters", into = "RpaJastrowParameters")]
+pub struct RpaJastrow {
+    cell: SimulationCell,
+    cutoff: f64,
+    num_up: usize,
+    parameters: Array1<f64>,
+}
+
+/// Serialized form of an `RpaJastrow`, without the cut-off that follows
+/// from the cell.
+#[derive(Serialize, Deserialize)]
+struct RpaJastrowParameters {
+    cell: SimulationCell,
+    num_up: usize,
+    parameters: Array1<f64>,
+}
+
+impl From<RpaJastrowParameters> for RpaJastrow {
+    fn from(rpa: RpaJastrowParameters) -> Self {
+        Self {
+            cutoff: RpaJastrow::inscribed_radius(&rpa.cell),
+            cell: rpa.cell,
+            num_up: rpa.num_up,
+            parameters: rpa.parameters,
+        }
+    }
+}
+
+impl From<RpaJastrow> for RpaJastrowParameters {
+    fn from(rpa: RpaJastrow) -> Self {
+        Self {
+            cell: rpa.cell,
+            num_up: rpa.num_up,
+            parameters: rpa.parameters,
+        }
+    }
+}
+
+impl RpaJastrow {
+    /// RPA Jastrow factor for electrons in `cell`, of which the first `num_up` are spin-up.
+    pub fn new(cell: SimulationCell, num_up: usize, a: f64) -> Self {
+        Self {
+            cutoff: Self::inscribed_radius(&cell),
+            cell,
+            num_up,
+            parameters: array![a],
+        }
+    }
+
+    /// Radius of the sphere inscribed in the Wigner-Seitz cell. It touches the
+    /// face bisecting the shortest lattice vector, which is no longer than
+    /// twice the typical cell size.
+    fn inscribed_radius(cell: &SimulationCell) -> f64 {
+        let size = cell.volume().powf(1.0 / cell.dimension() as f64);
+        0.5 * cell.lattice_points(2.0 * size)[1].norm_l2()
+    }
+
+    /// The exponent $f$ of the Jastrow factor.
+    pub fn exponent(&self, cfg: &Array2<f64>) -> f64 {
+        let mut f = 0.0;
+        self.for_each_pair(cfg, |_, _, (u, _, _, _), _, _| f -= u);
+        f
+    }
+
+    /// The gradient $\nabla f$ and laplacian $\nabla^2 f$ of the Jastrow exponent.
+    pub fn exponent_derivatives(&self, cfg: &Array2<f64>) -> (Array2<f64>, f64) {
+        let dim = cfg.len_of(Axis(1)) as f64;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        self.for_each_pair(cfg, |i, j, (_, du, d2u, _), r, rij| {
+            let unit = rij / r;
+            grad.row_mut(i).scaled_add(-du, &unit);
+            grad.row_mut(j).scaled_add(du, &unit);
+            lapl -= 2.0 * (d2u + (dim - 1.0) * du / r);
+        });
+        (grad, lapl)
+    }
+
+    /// Derivative of the Jastrow exponent with respect to $A$.
+    pub fn exponent_parameter_gradient(&self, cfg: &Array2<f64>) -> Array1<f64> {
+        let mut grad = 0.0;
+        self.for_each_pair(cfg, |_, _, (_, _, _, du_da), _, _| grad -= du_da);
+        array![grad]
+    }
+
+    fn names(&self) -> Vec<String> {
+        vec![String::from("A")]
+    }
+
+    /// The cut-off pair function of electrons at distance $r$, with like or
+    /// unlike spins, and its derivatives as in `rpa_pair_function`.
+    fn pair_function(&self, r: f64, like_spins: bool) -> (f64, f64, f64, f64) {
+        if r >= self.cutoff {
+            return (0.0, 0.0, 0.0, 0.0);
+        }
+        let a = self.parameters[0];
+        let f = if like_spins {
+            (2.0 * a).sqrt()
+        } else {
+            a.sqrt()
+        };
+        let l = self.cutoff;
+        let (u, du, d2u, du_da) = rpa_pair_function(r, a, f);
+        let (u_image, du_image, d2u_image, du_da_image) = rpa_pair_function(2.0 * l - r, a, f);
+        let (u_l, _, _, du_da_l) = rpa_pair_function(l, a, f);
+        (
+            u + u_image - 2.0 * u_l,
+            du - du_image,
+            d2u + d2u_image,
+            du_da + du_da_image - 2.0 * du_da_l,
+        )
+    }
+
+    /// Call `f` with the indices, pair function, minimum-image distance and
+    /// separation of each pair of electrons $i < j$.
+    fn for_each_pair<F>(&self, cfg: &Array2<f64>, mut f: F)
+    where
+        F: FnMut(usize, usize, (f64, f64, f64, f64), f64, &Array1<f64>),
+    {
+        let nelec = cfg.len_of(Axis(0));
+        for i in 0..nelec {
+            for j in i + 1..nelec {
+                let rij = self.cell.minimum_image(&(&cfg.row(i) - &cfg.row(j)));
+                let r = rij.norm_l2();
+                let like_spins = (i < self.num_up) == (j < self.num_up);
+                f(i, j, self.pair_function(r, like_spins), r, &rij);
+            }
+        }
+    }
+}
+
+impl_jastrow_factor!(RpaJastrow, "RPA Jastrow");
+
+impl Validate for Jastrow {
+    fn validate(&self) -> Result<()> {
+        check_invariant(self.parameters.len() >= 2, PADE_PARAMETERS)
+    }
+}
+
+impl Validate for ElectronNucleusJastrow {
+    fn validate(&self) -> Result<()> {
+        check_invariant(self.parameters.len() >= 2, PADE_PARAMETERS)
+    }
+}
+
+impl Validate for ElectronElectronNucleusJastrow {
+    fn validate(&self) -> Result<()> {
+        check_invariant(
+            self.parameters.len() == self.terms.len(),
+            "a three-body Jastrow factor needs one parameter per term",
+        )
+    }
+}
+
+impl Validate for McMillanJastrow {
+    fn validate(&self) -> Result<()> {
+        check_invariant(
+            self.parameters.len() == 1,
+            "a McMillan Jastrow factor has a single parameter",
+        )
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        Some(self.num_particles)
+    }
+}
+
+impl Validate for RpaJastrow {
+    fn validate(&self) -> Result<()> {
+        self.cell.validate()?;
+        check_invariant(
+            self.parameters.len() == 1,
+            "an RPA Jastrow factor has a single parameter",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: f64 = 1e-4;
+
+    fn configuration() -> Array2<f64> {
+        array![[0.1, 0.2, -0.3], [0.9, -0.1, 0.2], [-0.2, -0.4, 0.7]]
+    }
+
+    fn ion_positions() -> Array2<f64> {
+        array![[0.0, 0.0, 0.0], [0.7, -0.3, 0.4]]
+    }
+
+    fn configuration_2d() -> Array2<f64> {
+        array![[0.1, 0.2], [0.9, -0.1], [-0.2, -0.4]]
+    }
+
+    fn ion_positions_2d() -> Array2<f64> {
+        array![[0.0, 0.0], [0.7, -0.3]]
+    }
+
+    fn three_body(ion_positions: Array2<f64>) -> ElectronElectronNucleusJastrow {
+        let mut jastrow = ElectronElectronNucleusJastrow::new(ion_positions, 4, 0.8);
+        let nparm = jastrow.num_parameters();
+        jastrow.update_parameters(&Array1::linspace(-0.3, 0.4, nparm));
+        jastrow
+    }
+
+    fn rpa() -> RpaJastrow {
+        RpaJastrow::new(SimulationCell::cubic(3.0).unwrap(), 2, 0.8)
+    }
+
+    fn check_derivatives<T>(wf: &T, cfg: &Array2<f64>)
+    where
+        T: Function<f64, D = Ix2> + Differentiate<D = Ix2>,
+    {
+        let grad = wf.gradient(cfg).unwrap();
+        let value = wf.value(cfg).unwrap();
+        let mut lapl = 0.0;
+        for i in 0..cfg.len_of(Axis(0)) {
+            for k in 0..cfg.len_of(Axis(1)) {
+                let mut step = Array2::zeros(cfg.dim());
+                step[[i, k]] = H;
+                let forward = wf.value(&(cfg + &step)).unwrap();
+                let backward = wf.value(&(cfg - &step)).unwrap();
+                assert!((grad[[i, k]] - (forward - backward) / (2.0 * H)).abs() < 1e-6);
+                lapl += (forward - 2.0 * value + backward) / H.powi(2);
+            }
+        }
+        assert!((wf.laplacian(cfg).unwrap() - lapl).abs() < 1e-4);
+    }
+
+    fn check_parameter_gradient<T>(jastrow: &T)
+    where
+        T: Function<f64, D = Ix2> + Optimize + Clone,
+    {
+        let cfg = configuration();
+        let grad = jastrow.parameter_gradient(&cfg).unwrap();
+        for p in 0..jastrow.num_parameters() {
+            let mut step = Array1::zeros(jastrow.num_parameters());
+            step[p] = H;
+            let mut forward = jastrow.clone();
+            forward.update_parameters(&step);
+            let mut backward = jastrow.clone();
+            backward.update_parameters(&(-step));
+            let fd = (forward.value(&cfg).unwrap() - backward.value(&cfg).unwrap()) / (2.0 * H);
+            assert!((grad[p] - fd).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let cfg = configuration();
+        check_derivatives(
+            &Jastrow::new(array![0.5, 0.3, 0.1, -0.05], 0.8).unwrap(),
+            &cfg,
+        );
+        check_derivatives(
+            &ElectronNucleusJastrow::new(ion_positions(), array![-0.4, 0.6, 0.05], 1.1).unwrap(),
+            &cfg,
+        );
+        check_derivatives(&three_body(ion_positions()), &cfg);
+        check_derivatives(&McMillanJastrow::new(3, 0.5), &cfg);
+        check_derivatives(&rpa(), &cfg);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences_in_2d() {
+        let cfg = configuration_2d();
+        check_derivatives(
+            &Jastrow::new(array![0.5, 0.3, 0.1, -0.05], 0.8).unwrap(),
+            &cfg,
+        );
+        check_derivatives(
+            &ElectronNucleusJastrow::new(ion_positions_2d(), array![-0.4, 0.6, 0.05], 1.1).unwrap(),
+            &cfg,
+        );
+        check_derivatives(&three_body(ion_positions_2d()), &cfg);
+        check_derivatives(&McMillanJastrow::new(3, 0.5).with_dimension(2), &cfg);
+    }
+
+    #[test]
+    fn too_few_parameters() {
+        assert!(Jastrow::new(array![0.5], 1.0).is_err());
+        assert!(ElectronNucleusJastrow::new(ion_positions(), array![0.5], 1.0).is_err());
+    }
+
+    #[test]
+    fn parameter_gradient_matches_finite_differences() {
+        check_parameter_gradient(&Jastrow::new(array![0.5, 0.3, 0.1, -0.05], 0.8).unwrap());
+        check_parameter_gradient(
+            &ElectronNucleusJastrow::new(ion_positions(), array![-0.4, 0.6, 0.05], 1.1).unwrap(),
+        );
+        check_parameter_gradient(&three_body(ion_positions()));
+        check_parameter_gradient(&McMillanJastrow::new(3, 0.5));
+        check_parameter_gradient(&rpa());
+    }
+}
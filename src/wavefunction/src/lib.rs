@@ -0,0 +1,22 @@
+#[allow(unused_imports)]
+#[macro_use]
+extern crate ndarray;
+extern crate ndarray_linalg;
+
+mod backflow;
+mod cusp;
+mod determinant;
+mod heg;
+mod jastrow;
+mod molden;
+mod orbital_product;
+mod orbitals;
+
+pub use crate::backflow::*;
+pub use crate::cusp::*;
+pub use crate::determinant::*;
+pub use crate::heg::*;
+pub use crate::jastrow::*;
+pub use crate::molden::*;
+pub use crate::orbital_product::*;
+pub use crate::orbitals::*;
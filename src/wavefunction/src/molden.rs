@@ -0,0 +1,535 @@
+// Standard imports
+use std::fs;
+use std::path::Path;
+// Third party imports
+use ndarray::{Array1, Array2};
+// First party imports
+use crate::cusp::CuspCorrectedOrbital;
+use crate::determinant::SpinDeterminantProduct;
+use crate::orbitals::{AngularPart, GaussianOrbital};
+use errors::Error::{self, ParseError};
+use operator::ElectronicHamiltonian;
+use wavefunction_traits::LinearCombination;
+
+type Result<T> = std::result::Result<T, Error>;
+type MolecularOrbitalFunction = LinearCombination<GaussianOrbital>;
+
+/// Conversion factor from Angstrom to bohr.
+const ANGSTROM_TO_BOHR: f64 = 1.0 / 0.529_177_210_92;
+
+/// Spin of a molecular orbital.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spin {
+    Alpha,
+    Beta,
+}
+
+/// Molecular orbital read from the `[MO]` section of a Molden file,
+/// expanded in the basis functions of the `[GTO]` section.
+#[derive(Clone, Debug)]
+pub struct MolecularOrbital {
+    pub energy: f64,
+    pub spin: Spin,
+    pub occupation: f64,
+    pub coefficients: Array1<f64>,
+}
+
+/// Contents of a Molden file: the molecular geometry, the Gaussian basis set
+/// and the molecular orbitals. Positions are stored in bohr. The contraction
+/// coefficients of each shell are rescaled such that the contracted basis
+/// functions are normalized, which is what the MO coefficients assume.
+#[derive(Clone, Debug)]
+pub struct Molden {
+    ion_positions: Array2<f64>,
+    ion_charges: Array1<i32>,
+    basis: Vec<GaussianOrbital>,
+    orbitals: Vec<MolecularOrbital>,
+}
+
+/// Contracted shell of Gaussian primitives on a single atom.
+struct Shell {
+    atom: usize,
+    l: i32,
+    exponents: Vec<f64>,
+    coefficients: Vec<f64>,
+}
+
+fn parse_error(msg: &str, line: &str) -> Error {
+    ParseError(format!("{}: '{}'", msg, line.trim()))
+}
+
+/// Parse a floating point number, allowing Fortran-style exponents such as `1.0D-02`.
+fn parse_float(token: &str) -> Result<f64> {
+    token
+        .replace('D', "E")
+        .replace('d', "e")
+        .parse()
+        .map_err(|_| parse_error("Invalid number", token))
+}
+
+fn parse_int<T: std::str::FromStr>(token: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| parse_error("Invalid integer", token))
+}
+
+impl Molden {
+    /// Read a Molden file from disk.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the contents of a Molden file. The `[Atoms]`, `[GTO]` and `[MO]`
+    /// sections are required; Cartesian functions are used unless the
+    /// `[5D]`, `[5D7F]`, `[5D10F]` or `[7F]` flags are present.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+        for line in contents.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                sections.push((trimmed.to_lowercase(), Vec::new()));
+            } else if let Some((_, lines)) = sections.last_mut() {
+                lines.push(line);
+            }
+        }
+
+        let find_section = |name: &str| {
+            sections
+                .iter()
+                .find(|(header, _)| header.starts_with(name))
+                .ok_or_else(|| ParseError(format!("Missing section {}", name)))
+        };
+        let has_flag = |name: &str| sections.iter().any(|(header, _)| header == name);
+
+        let (atoms_header, atom_lines) = find_section("[atoms]")?;
+        let (ion_positions, ion_charges) = Self::parse_atoms(atoms_header, atom_lines)?;
+        let shells = Self::parse_gto(&find_section("[gto]")?.1)?;
+
+        let spherical_d = has_flag("[5d]") || has_flag("[5d7f]") || has_flag("[5d10f]");
+        let spherical_f = has_flag("[5d]") || has_flag("[5d7f]") || has_flag("[7f]");
+        let mut basis = Vec::new();
+        for shell in shells {
+            if shell.atom >= ion_charges.len() {
+                return Err(ParseError(format!(
+                    "Basis set given for nonexistent atom {}",
+                    shell.atom + 1
+                )));
+            }
+            let center = ion_positions.row(shell.atom).to_owned();
+            let spherical = match shell.l {
+                2 => spherical_d,
+                3 => spherical_f,
+                _ => false,
+            };
+            let coefficients = Self::normalize_contraction(&shell);
+            for angular in Self::molden_shell(shell.l, spherical) {
+                basis.push(GaussianOrbital::new(
+                    center.clone(),
+                    Array1::from_vec(shell.exponents.clone()),
+                    coefficients.clone(),
+                    angular,
+                ));
+            }
+        }
+
+        let orbitals = Self::parse_mo(&find_section("[mo]")?.1, basis.len())?;
+
+        Ok(Self {
+            ion_positions,
+            ion_charges,
+            basis,
+            orbitals,
+        })
+    }
+
+    fn parse_atoms(header: &str, lines: &[&str]) -> Result<(Array2<f64>, Array1<i32>)> {
+        let scale = if header.contains("angs") {
+            ANGSTROM_TO_BOHR
+        } else {
+            1.0
+        };
+        let mut positions = Vec::new();
+        let mut charges = Vec::new();
+        for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            if tokens.len() != 6 {
+                return Err(parse_error("Invalid atom specification", line));
+            }
+            charges.push(parse_int(tokens[2])?);
+            for token in &tokens[3..] {
+                positions.push(scale * parse_float(token)?);
+            }
+        }
+        let num_atoms = charges.len();
+        Ok((
+            Array2::from_shape_vec((num_atoms, 3), positions)?,
+            Array1::from_vec(charges),
+        ))
+    }
+
+    fn parse_gto(lines: &[&str]) -> Result<Vec<Shell>> {
+        let mut shells = Vec::new();
+        let mut atom = None;
+        let mut lines = lines.iter().filter(|l| !l.trim().is_empty());
+        while let Some(line) = lines.next() {
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            let l = match tokens[0].to_lowercase().as_str() {
+                "s" => 0,
+                "p" => 1,
+                "d" => 2,
+                "f" => 3,
+                label if label.parse::<usize>().is_ok() => {
+                    // atom sequence numbers start at one
+                    let index: usize = parse_int(label)?;
+                    atom = Some(
+                        index
+                            .checked_sub(1)
+                            .ok_or_else(|| parse_error("Invalid atom index", line))?,
+                    );
+                    continue;
+                }
+                _ => return Err(parse_error("Unsupported shell", line)),
+            };
+            if tokens.len() < 2 {
+                return Err(parse_error("Missing number of primitives", line));
+            }
+            let num_primitives: usize = parse_int(tokens[1])?;
+            let scale = match tokens.get(2) {
+                Some(token) => parse_float(token)?,
+                None => 1.0,
+            };
+            let mut exponents = Vec::new();
+            let mut coefficients = Vec::new();
+            for _ in 0..num_primitives {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| ParseError("Unexpected end of [GTO] section".to_string()))?;
+                let tokens: Vec<_> = line.split_whitespace().collect();
+                if tokens.len() != 2 {
+                    return Err(parse_error("Invalid primitive", line));
+                }
+                exponents.push(parse_float(tokens[0])? * scale.powi(2));
+                coefficients.push(parse_float(tokens[1])?);
+            }
+            shells.push(Shell {
+                atom: atom.ok_or_else(|| parse_error("Shell without atom", line))?,
+                l,
+                exponents,
+                coefficients,
+            });
+        }
+        Ok(shells)
+    }
+
+    fn parse_mo(lines: &[&str], num_basis: usize) -> Result<Vec<MolecularOrbital>> {
+        let new_orbital = || MolecularOrbital {
+            energy: 0.0,
+            spin: Spin::Alpha,
+            occupation: 0.0,
+            coefficients: Array1::zeros(num_basis),
+        };
+        let mut orbitals = Vec::new();
+        let mut current = new_orbital();
+        let mut reading_coefficients = false;
+        for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+            if let Some(pos) = line.find('=') {
+                if reading_coefficients {
+                    orbitals.push(current);
+                    current = new_orbital();
+                    reading_coefficients = false;
+                }
+                let value = line[pos + 1..].trim();
+                match line[..pos].trim().to_lowercase().as_str() {
+                    "ene" => current.energy = parse_float(value)?,
+                    "occup" => current.occupation = parse_float(value)?,
+                    "spin" => {
+                        current.spin = match value.to_lowercase().as_str() {
+                            "alpha" => Spin::Alpha,
+                            "beta" => Spin::Beta,
+                            _ => return Err(parse_error("Invalid spin", line)),
+                        }
+                    }
+                    _ => (),
+                }
+            } else {
+                let tokens: Vec<_> = line.split_whitespace().collect();
+                if tokens.len() != 2 {
+                    return Err(parse_error("Invalid MO coefficient", line));
+                }
+                let index: usize = parse_int(tokens[0])?;
+                if index == 0 || index > num_basis {
+                    return Err(parse_error("MO coefficient index out of range", line));
+                }
+                current.coefficients[index - 1] = parse_float(tokens[1])?;
+                reading_coefficients = true;
+            }
+        }
+        if reading_coefficients {
+            orbitals.push(current);
+        }
+        Ok(orbitals)
+    }
+
+    /// Contraction coefficients of a shell of normalized primitives, scaled such
+    /// that the contracted function is normalized as well. Normalized primitives
+    /// with exponents $\alpha$ and $\beta$ overlap by
+    /// $\left(2\sqrt{\alpha\beta}/(\alpha + \beta)\right)^{l + 3/2}$.
+    fn normalize_contraction(shell: &Shell) -> Array1<f64> {
+        let coefficients = Array1::from_vec(shell.coefficients.clone());
+        let mut norm = 0.0;
+        for (a, da) in shell.exponents.iter().zip(shell.coefficients.iter()) {
+            for (b, db) in shell.exponents.iter().zip(shell.coefficients.iter()) {
+                let overlap = (2.0 * (a * b).sqrt() / (a + b)).powf(f64::from(shell.l) + 1.5);
+                norm += da * db * overlap;
+            }
+        }
+        coefficients / norm.sqrt()
+    }
+
+    /// Angular parts of a shell, in the order used by the Molden format.
+    fn molden_shell(l: i32, spherical: bool) -> Vec<AngularPart> {
+        if spherical {
+            // m = 0, +1, -1, +2, -2, ...
+            let mut shell = vec![AngularPart::spherical(l, 0)];
+            for m in 1..=l {
+                shell.push(AngularPart::spherical(l, m));
+                shell.push(AngularPart::spherical(l, -m));
+            }
+            return shell;
+        }
+        let powers: &[[i32; 3]] = match l {
+            0 => &[[0, 0, 0]],
+            1 => &[[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            2 => &[
+                [2, 0, 0],
+                [0, 2, 0],
+                [0, 0, 2],
+                [1, 1, 0],
+                [1, 0, 1],
+                [0, 1, 1],
+            ],
+            3 => &[
+                [3, 0, 0],
+                [0, 3, 0],
+                [0, 0, 3],
+                [1, 2, 0],
+                [2, 1, 0],
+                [2, 0, 1],
+                [1, 0, 2],
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 1, 1],
+            ],
+            _ => unreachable!(),
+        };
+        powers.iter().map(|&p| AngularPart::cartesian(p)).collect()
+    }
+
+    pub fn ion_positions(&self) -> &Array2<f64> {
+        &self.ion_positions
+    }
+
+    pub fn ion_charges(&self) -> &Array1<i32> {
+        &self.ion_charges
+    }
+
+    pub fn basis(&self) -> &[GaussianOrbital] {
+        &self.basis
+    }
+
+    pub fn orbitals(&self) -> &[MolecularOrbital] {
+        &self.orbitals
+    }
+
+    /// Build a determinant wave function from the occupied orbitals. Alpha
+    /// orbitals with occupation close to two are placed in both the spin-up and
+    /// spin-down determinants, singly occupied alpha orbitals in the spin-up
+    /// determinant, and occupied beta orbitals in the spin-down determinant.
+    pub fn wave_function(
+        &self,
+    ) -> Result<SpinDeterminantProduct<LinearCombination<GaussianOrbital>>> {
+        let (up, down) = self.occupied_orbitals()?;
+        Ok(SpinDeterminantProduct::new(up, down))
+    }
+
+    /// As `wave_function`, with each orbital cusp corrected within `radius`
+    /// of the ions.
+    pub fn cusp_corrected_wave_function(
+        &self,
+        radius: f64,
+    ) -> Result<SpinDeterminantProduct<CuspCorrectedOrbital>> {
+        let correct = |orbitals: Vec<_>| {
+            orbitals
+                .into_iter()
+                .map(|mo| {
+                    CuspCorrectedOrbital::new(mo, &self.ion_positions, &self.ion_charges, radius)
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let (up, down) = self.occupied_orbitals()?;
+        Ok(SpinDeterminantProduct::new(correct(up)?, correct(down)?))
+    }
+
+    fn occupied_orbitals(
+        &self,
+    ) -> Result<(Vec<MolecularOrbitalFunction>, Vec<MolecularOrbitalFunction>)> {
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        for orbital in &self.orbitals {
+            let mo = LinearCombination::new(orbital.coefficients.clone(), self.basis.clone())?;
+            match orbital.spin {
+                Spin::Alpha if orbital.occupation > 1.5 => {
+                    up.push(mo.clone());
+                    down.push(mo);
+                }
+                Spin::Alpha if orbital.occupation > 0.5 => up.push(mo),
+                Spin::Beta if orbital.occupation > 0.5 => down.push(mo),
+                _ => (),
+            }
+        }
+        Ok((up, down))
+    }
+
+    /// Electronic Hamiltonian for the molecular geometry in this file.
+    pub fn hamiltonian(&self) -> ElectronicHamiltonian {
+        ElectronicHamiltonian::from_ions(self.ion_positions.clone(), self.ion_charges.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+    use wavefunction_traits::{Function, WaveFunction};
+
+    // H2 at 0.74 Angstrom in the STO-3G basis
+    const H2_STO3G: &str = "\
+[Molden Format]
+[Atoms] Angs
+H     1    1     0.000000     0.000000    -0.370000
+H     2    1     0.000000     0.000000     0.370000
+[GTO]
+  1 0
+ s    3 1.00
+      3.42525091  0.15432897
+      0.62391373  0.53532814
+      0.16885540  0.44463454
+
+  2 0
+ s    3 1.00
+      3.42525091D+00  0.15432897D+00
+      0.62391373D+00  0.53532814D+00
+      0.16885540D+00  0.44463454D+00
+
+[MO]
+ Sym= Ag
+ Ene= -0.5782
+ Spin= Alpha
+ Occup= 2.000000
+   1   0.548302
+   2   0.548302
+ Sym= B1u
+ Ene= 0.6703
+ Spin= Alpha
+ Occup= 0.000000
+   1   1.218327
+   2  -1.218327
+";
+
+    #[test]
+    fn parse_h2() {
+        let molden = Molden::parse(H2_STO3G).unwrap();
+        let bond = 0.74 * ANGSTROM_TO_BOHR;
+        assert!(
+            (molden.ion_positions()[[1, 2]] - molden.ion_positions()[[0, 2]] - bond).abs() < 1e-12
+        );
+        assert_eq!(molden.ion_charges(), &array![1, 1]);
+        assert_eq!(molden.basis().len(), 2);
+        assert_eq!(molden.orbitals().len(), 2);
+        assert_eq!(
+            molden.orbitals()[1].coefficients,
+            array![1.218327, -1.218327]
+        );
+
+        let wf = molden.wave_function().unwrap();
+        assert_eq!(wf.num_electrons(), 2);
+        assert_eq!(wf.num_up(), 1);
+
+        // closed shell: $\psi(r_1, r_2) = \phi(r_1)\phi(r_2)$
+        let cfg = array![[0.1, -0.2, 0.3], [-0.4, 0.1, -0.5]];
+        let sigma =
+            LinearCombination::new(array![0.548302, 0.548302], molden.basis().to_vec()).unwrap();
+        let expected = sigma.value(&cfg.row(0).to_owned()).unwrap()
+            * sigma.value(&cfg.row(1).to_owned()).unwrap();
+        assert!((wf.value(&cfg).unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cusp_corrected_h2() {
+        let molden = Molden::parse(H2_STO3G).unwrap();
+        let wf = molden.wave_function().unwrap();
+        let corrected = molden.cusp_corrected_wave_function(0.2).unwrap();
+        // corrections only apply within the cusp radius of an ion
+        let far = array![[0.1, -0.2, 0.3], [-0.4, 0.1, -0.5]];
+        assert_eq!(wf.value(&far).unwrap(), corrected.value(&far).unwrap());
+        let near = array![[0.05, 0.0, 0.7], [-0.4, 0.1, -0.5]];
+        assert!((wf.value(&near).unwrap() - corrected.value(&near).unwrap()).abs() > 1e-6);
+    }
+
+    #[test]
+    fn spherical_shells() {
+        let contents = "\
+[Atoms] AU
+O 1 8 0.0 0.0 0.0
+[5D]
+[GTO]
+1 0
+d 1 1.0
+0.8 1.0
+f 1 1.0
+0.5 1.0
+[MO]
+Ene= -1.0
+Spin= Beta
+Occup= 1.0
+1 1.0
+";
+        let molden = Molden::parse(contents).unwrap();
+        assert_eq!(molden.basis().len(), 12);
+        assert_eq!(molden.orbitals()[0].spin, Spin::Beta);
+        assert_eq!(molden.wave_function().unwrap().num_up(), 0);
+        assert_eq!(molden.wave_function().unwrap().num_down(), 1);
+    }
+
+    #[test]
+    fn contracted_basis_functions_are_normalized() {
+        let contents = "\
+[Atoms] AU
+He 1 2 0.0 0.0 0.0
+[GTO]
+1 0
+s 2 1.0
+6.36 1.0
+1.16 1.0
+[MO]
+Occup= 2.0
+1 1.0
+";
+        let molden = Molden::parse(contents).unwrap();
+        // $4\pi \int r^2 \phi(r)^2 dr$ by the midpoint rule
+        let dr = 1e-3;
+        let norm: f64 = (0..10000)
+            .map(|i| {
+                let r = (i as f64 + 0.5) * dr;
+                let phi = molden.basis()[0].value(&array![0.0, 0.0, r]).unwrap();
+                4.0 * std::f64::consts::PI * r.powi(2) * phi.powi(2) * dr
+            })
+            .sum();
+        assert!((norm - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn missing_section() {
+        assert!(Molden::parse("[Atoms] AU\nH 1 1 0.0 0.0 0.0\n").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+// Third party imports
+use ndarray::{Array1, Array2, Ix1, Ix2};
+use serde::{Deserialize, Serialize};
+// First party imports
+use errors::Error;
+use wavefunction_traits::{Differentiate, Function, LogVgl, Validate, Vgl, WaveFunction};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Product of single-particle orbitals,
+/// $\psi(\mathbf{r}_1, \dots, \mathbf{r}_N) = \prod_i \phi_i(\mathbf{r}_i)$,
+/// for distinguishable particles or, with all orbitals equal, for bosons
+/// condensed in a single orbital. Pair correlations can be added by
+/// multiplying with a Jastrow factor, e.g. `McMillanJastrow`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OrbitalProduct<T> {
+    orbitals: Vec<T>,
+    dimension: usize,
+}
+
+impl<T> OrbitalProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    pub fn new(orbitals: Vec<T>) -> Self {
+        Self {
+            orbitals,
+            dimension: 3,
+        }
+    }
+
+    /// Symmetric product of `num_particles` copies of `orbital`.
+    pub fn bosonic(orbital: T, num_particles: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(vec![orbital; num_particles])
+    }
+
+    /// Set the number of spatial dimensions of the orbitals, three by default.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = dimension;
+        self
+    }
+
+    pub fn orbitals(&self) -> &[T] {
+        &self.orbitals
+    }
+}
+
+impl<T> Function<f64> for OrbitalProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        let mut value = 1.0;
+        for (orbital, pos) in self.orbitals.iter().zip(cfg.outer_iter()) {
+            value *= orbital.value(&pos.to_owned())?;
+        }
+        Ok(value)
+    }
+
+    fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+        let mut log_value = 0.0;
+        let mut sign = 1.0;
+        for (orbital, pos) in self.orbitals.iter().zip(cfg.outer_iter()) {
+            let value = orbital.value(&pos.to_owned())?;
+            log_value += value.abs().ln();
+            sign *= value.signum();
+        }
+        Ok((log_value, sign))
+    }
+}
+
+impl<T> Differentiate for OrbitalProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(self.vgl(cfg)?.1)
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(self.vgl(cfg)?.2)
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        let (log_value, sign, grad, lapl) = self.log_vgl(cfg)?;
+        let value = sign * log_value.exp();
+        let grad_squared = (&grad * &grad).sum();
+        Ok((value, value * grad, value * (lapl + grad_squared)))
+    }
+
+    /// Each particle contributes $\nabla_i \ln|\phi_i|$ and
+    /// $\nabla^2 \phi_i / \phi_i - |\nabla_i \ln|\phi_i||^2$ independently.
+    fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+        let mut log_value = 0.0;
+        let mut sign = 1.0;
+        let mut grad = Array2::<f64>::zeros(cfg.dim());
+        let mut lapl = 0.0;
+        for ((orbital, pos), mut grad_i) in self
+            .orbitals
+            .iter()
+            .zip(cfg.outer_iter())
+            .zip(grad.outer_iter_mut())
+        {
+            let (value, grad_phi, lapl_phi) = orbital.vgl(&pos.to_owned())?;
+            log_value += value.abs().ln();
+            sign *= value.signum();
+            grad_i.assign(&(grad_phi / value));
+            lapl += lapl_phi / value - grad_i.dot(&grad_i);
+        }
+        Ok((log_value, sign, grad, lapl))
+    }
+}
+
+impl<T> WaveFunction for OrbitalProduct<T>
+where
+    T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+{
+    fn num_electrons(&self) -> usize {
+        self.orbitals.len()
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Only the orbital of the moved particle changes.
+    fn ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<f64> {
+        let orbital = &self.orbitals[idx];
+        Ok(orbital.value(pos)? / orbital.value(&cfg.row(idx).to_owned())?)
+    }
+
+    fn gradient_ratio(
+        &self,
+        _cfg: &Array2<f64>,
+        pos: &Array1<f64>,
+        idx: usize,
+    ) -> Result<Array1<f64>> {
+        let (value, grad, _) = self.orbitals[idx].vgl(pos)?;
+        Ok(grad / value)
+    }
+}
+
+impl<T: Validate> Validate for OrbitalProduct<T> {
+    fn validate(&self) -> Result<()> {
+        self.orbitals.iter().try_for_each(Validate::validate)
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        Some(self.orbitals.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbitals::{AngularPart, GaussianOrbital};
+    use wavefunction_traits::check_derivatives;
+
+    fn product() -> OrbitalProduct<GaussianOrbital> {
+        let orbital = GaussianOrbital::new(
+            array![0.1, 0.0, -0.2],
+            array![1.2, 0.4],
+            array![0.3, 0.7],
+            AngularPart::spherical(0, 0),
+        );
+        OrbitalProduct::bosonic(orbital, 3)
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let cfg = array![[0.3, 0.1, -0.4], [-0.5, 0.6, 0.2], [0.1, -0.7, 0.5]];
+        let check = check_derivatives(&product(), &cfg, 1e-3).unwrap();
+        assert!(check.passes(1e-6), "{:?}", check);
+    }
+
+    #[test]
+    fn symmetric_under_exchange() {
+        let wf = product();
+        let cfg = array![[0.3, 0.1, -0.4], [-0.5, 0.6, 0.2], [0.1, -0.7, 0.5]];
+        let swapped = array![[-0.5, 0.6, 0.2], [0.3, 0.1, -0.4], [0.1, -0.7, 0.5]];
+        assert!((wf.value(&cfg).unwrap() - wf.value(&swapped).unwrap()).abs() < 1e-14);
+
+        let pos = array![0.2, 0.2, 0.2];
+        let mut moved = cfg.clone();
+        moved.row_mut(1).assign(&pos);
+        let ratio = wf.value(&moved).unwrap() / wf.value(&cfg).unwrap();
+        assert!((wf.ratio(&cfg, &pos, 1).unwrap() - ratio).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,555 @@
+// Third party imports
+use ndarray::{Array1, Array2, Ix1};
+use ndarray_linalg::Norm;
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::jastrow::monomial;
+use errors::Error;
+use wavefunction_traits::{check_invariant, Differentiate, Function, Hessian, Validate, Vgl};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Double factorial $n!!$, with $n!! = 1$ for $n \leq 0$.
+fn double_factorial(n: i32) -> f64 {
+    (1..=n).rev().step_by(2).map(f64::from).product()
+}
+
+/// Integral of $x^a y^b z^c$ over the unit sphere.
+fn sphere_integral(powers: [i32; 3]) -> f64 {
+    if powers.iter().any(|p| p % 2 == 1) {
+        return 0.0;
+    }
+    let [a, b, c] = powers;
+    4.0 * std::f64::consts::PI
+        * double_factorial(a - 1)
+        * double_factorial(b - 1)
+        * double_factorial(c - 1)
+        / double_factorial(a + b + c + 1)
+}
+
+/// Angular part of an orbital: a homogeneous polynomial
+/// $P(x, y, z) = \sum_t c_t x^{a_t} y^{b_t} z^{c_t}$ of degree $l$,
+/// normalized such that $\int |P|^2 d\Omega = 1$ on the unit sphere.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AngularPart {
+    terms: Vec<(f64, [i32; 3])>,
+    degree: i32,
+}
+
+impl AngularPart {
+    /// Construct a normalized angular part from a list of monomials
+    /// $(c_t, [a_t, b_t, c_t])$, all of which must have the same degree.
+    pub fn new(terms: Vec<(f64, [i32; 3])>) -> Self {
+        assert!(!terms.is_empty());
+        let degree = terms[0].1.iter().sum();
+        assert!(terms.iter().all(|(_, p)| p.iter().sum::<i32>() == degree));
+        let mut norm = 0.0;
+        for (ct, pt) in &terms {
+            for (cs, ps) in &terms {
+                norm += ct * cs * sphere_integral([pt[0] + ps[0], pt[1] + ps[1], pt[2] + ps[2]]);
+            }
+        }
+        let norm = norm.sqrt();
+        Self {
+            terms: terms.into_iter().map(|(c, p)| (c / norm, p)).collect(),
+            degree,
+        }
+    }
+
+    /// Cartesian angular part $x^a y^b z^c$.
+    pub fn cartesian(powers: [i32; 3]) -> Self {
+        Self::new(vec![(1.0, powers)])
+    }
+
+    /// Real solid harmonic $r^l Y_{lm}$, for $l \leq 3$.
+    pub fn spherical(l: i32, m: i32) -> Self {
+        let terms = match (l, m) {
+            (0, 0) => vec![(1.0, [0, 0, 0])],
+            (1, -1) => vec![(1.0, [0, 1, 0])],
+            (1, 0) => vec![(1.0, [0, 0, 1])],
+            (1, 1) => vec![(1.0, [1, 0, 0])],
+            (2, -2) => vec![(1.0, [1, 1, 0])],
+            (2, -1) => vec![(1.0, [0, 1, 1])],
+            (2, 0) => vec![(2.0, [0, 0, 2]), (-1.0, [2, 0, 0]), (-1.0, [0, 2, 0])],
+            (2, 1) => vec![(1.0, [1, 0, 1])],
+            (2, 2) => vec![(1.0, [2, 0, 0]), (-1.0, [0, 2, 0])],
+            (3, -3) => vec![(3.0, [2, 1, 0]), (-1.0, [0, 3, 0])],
+            (3, -2) => vec![(1.0, [1, 1, 1])],
+            (3, -1) => vec![(4.0, [0, 1, 2]), (-1.0, [2, 1, 0]), (-1.0, [0, 3, 0])],
+            (3, 0) => vec![(2.0, [0, 0, 3]), (-3.0, [2, 0, 1]), (-3.0, [0, 2, 1])],
+            (3, 1) => vec![(4.0, [1, 0, 2]), (-1.0, [3, 0, 0]), (-1.0, [1, 2, 0])],
+            (3, 2) => vec![(1.0, [2, 0, 1]), (-1.0, [0, 2, 1])],
+            (3, 3) => vec![(1.0, [3, 0, 0]), (-3.0, [1, 2, 0])],
+            _ => panic!("Spherical harmonics are only available for l <= 3, |m| <= l"),
+        };
+        Self::new(terms)
+    }
+
+    /// All Cartesian angular parts of degree $l$, in lexicographic order
+    /// of the powers of $x$, $y$ and $z$, from high to low.
+    pub fn cartesian_shell(l: i32) -> Vec<Self> {
+        let mut shell = Vec::new();
+        for a in (0..=l).rev() {
+            for b in (0..=l - a).rev() {
+                shell.push(Self::cartesian([a, b, l - a - b]));
+            }
+        }
+        shell
+    }
+
+    /// All real solid harmonics of degree $l$, ordered from $m = -l$ to $m = l$.
+    pub fn spherical_shell(l: i32) -> Vec<Self> {
+        (-l..=l).map(|m| Self::spherical(l, m)).collect()
+    }
+
+    pub fn degree(&self) -> i32 {
+        self.degree
+    }
+
+    /// Value, gradient and laplacian of the polynomial at $x$.
+    fn derivatives(&self, x: &Array1<f64>) -> (f64, Array1<f64>, f64) {
+        let mut value = 0.0;
+        let mut grad = Array1::<f64>::zeros(3);
+        let mut lapl = 0.0;
+        for (c, p) in &self.terms {
+            let (px, dpx, d2px) = monomial(x[0], p[0]);
+            let (py, dpy, d2py) = monomial(x[1], p[1]);
+            let (pz, dpz, d2pz) = monomial(x[2], p[2]);
+            value += c * px * py * pz;
+            grad[0] += c * dpx * py * pz;
+            grad[1] += c * px * dpy * pz;
+            grad[2] += c * px * py * dpz;
+            lapl += c * (d2px * py * pz + px * d2py * pz + px * py * d2pz);
+        }
+        (value, grad, lapl)
+    }
+
+    /// Hessian matrix of the polynomial at $x$.
+    fn hessian(&self, x: &Array1<f64>) -> Array2<f64> {
+        let mut hessian = Array2::<f64>::zeros((3, 3));
+        for (c, p) in &self.terms {
+            let factors: Vec<_> = (0..3).map(|k| monomial(x[k], p[k])).collect();
+            for a in 0..3 {
+                for b in 0..3 {
+                    let mut term = *c;
+                    for (k, (value, deriv, second)) in factors.iter().enumerate() {
+                        term *= match (k == a, k == b) {
+                            (true, true) => *second,
+                            (true, false) | (false, true) => *deriv,
+                            (false, false) => *value,
+                        };
+                    }
+                    hessian[[a, b]] += term;
+                }
+            }
+        }
+        hessian
+    }
+
+    /// Combine the polynomial with a radial function $R(r)$, given $R$, $R'/r$ and $R''$,
+    /// into the value, gradient and laplacian of $R(r) P(x)$.
+    fn combine(&self, x: &Array1<f64>, radial: (f64, f64, f64)) -> (f64, Array1<f64>, f64) {
+        let (rad, drad_r, d2rad) = radial;
+        let (poly, grad_poly, lapl_poly) = self.derivatives(x);
+        let grad = rad * grad_poly + poly * drad_r * x;
+        // $P$ is homogeneous of degree $l$, so $x \cdot \nabla P = l P$
+        let lapl = rad * lapl_poly
+            + 2.0 * drad_r * f64::from(self.degree) * poly
+            + poly * (d2rad + 2.0 * drad_r);
+        (rad * poly, grad, lapl)
+    }
+
+    /// Hessian matrix of $R(r) P(x)$, with the radial function given as in `combine`.
+    fn combine_hessian(&self, x: &Array1<f64>, radial: (f64, f64, f64)) -> Array2<f64> {
+        let (rad, drad_r, d2rad) = radial;
+        let (poly, grad_poly, _) = self.derivatives(x);
+        let r2 = x.dot(x);
+        let mut hessian = rad * self.hessian(x);
+        for a in 0..3 {
+            for b in 0..3 {
+                hessian[[a, b]] += drad_r * (x[a] * grad_poly[b] + x[b] * grad_poly[a])
+                    + poly * (d2rad - drad_r) * x[a] * x[b] / r2;
+            }
+            hessian[[a, a]] += poly * drad_r;
+        }
+        hessian
+    }
+}
+
+/// Normalized Slater-type orbital
+/// $\chi(\mathbf{r}) = N r^{n - l - 1} e^{-\zeta r} P(\mathbf{r} - \mathbf{R})$,
+/// centered at $\mathbf{R}$, with $r = |\mathbf{r} - \mathbf{R}|$, principal
+/// quantum number $n$ and angular part $P$ of degree $l < n$.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlaterOrbital {
+    center: Array1<f64>,
+    n: i32,
+    exponent: f64,
+    angular: AngularPart,
+}
+
+impl SlaterOrbital {
+    pub fn new(center: Array1<f64>, n: i32, exponent: f64, angular: AngularPart) -> Self {
+        assert!(n > angular.degree());
+        Self {
+            center,
+            n,
+            exponent,
+            angular,
+        }
+    }
+
+    /// Radial normalization $N = \sqrt{(2\zeta)^{2n + 1} / (2n)!}$.
+    fn normalization(&self) -> f64 {
+        let factorial: f64 = (1..=2 * self.n).map(f64::from).product();
+        f64::sqrt((2.0 * self.exponent).powi(2 * self.n + 1) / factorial)
+    }
+
+    /// Radial function $R$, $R'/r$ and $R''$.
+    fn radial(&self, r: f64) -> (f64, f64, f64) {
+        let k = f64::from(self.n - self.angular.degree() - 1);
+        let zeta = self.exponent;
+        let rad = self.normalization() * r.powf(k) * f64::exp(-zeta * r);
+        let log_deriv = k / r - zeta;
+        (
+            rad,
+            rad * log_deriv / r,
+            rad * (log_deriv.powi(2) - k / r.powi(2)),
+        )
+    }
+
+    /// Derivative of the orbital value with respect to the exponent $\zeta$.
+    pub fn exponent_derivative(&self, pos: &Array1<f64>) -> Result<f64> {
+        let r = (pos - &self.center).norm_l2();
+        let dlog_norm = f64::from(2 * self.n + 1) / (2.0 * self.exponent);
+        Ok(self.value(pos)? * (dlog_norm - r))
+    }
+
+    pub fn exponent(&self) -> f64 {
+        self.exponent
+    }
+
+    pub fn center(&self) -> &Array1<f64> {
+        &self.center
+    }
+}
+
+impl Function<f64> for SlaterOrbital {
+    type D = Ix1;
+
+    fn value(&self, pos: &Array1<f64>) -> Result<f64> {
+        let x = pos - &self.center;
+        let (rad, _, _) = self.radial(x.norm_l2());
+        Ok(rad * self.angular.derivatives(&x).0)
+    }
+}
+
+impl Differentiate for SlaterOrbital {
+    type D = Ix1;
+
+    fn gradient(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        Ok(self.vgl(pos)?.1)
+    }
+
+    fn laplacian(&self, pos: &Array1<f64>) -> Result<f64> {
+        Ok(self.vgl(pos)?.2)
+    }
+
+    fn vgl(&self, pos: &Array1<f64>) -> Result<Vgl<Ix1>> {
+        let x = pos - &self.center;
+        Ok(self.angular.combine(&x, self.radial(x.norm_l2())))
+    }
+}
+
+impl Hessian for SlaterOrbital {
+    fn hessian(&self, pos: &Array1<f64>) -> Result<Array2<f64>> {
+        let x = pos - &self.center;
+        Ok(self.angular.combine_hessian(&x, self.radial(x.norm_l2())))
+    }
+}
+
+/// Contracted Gaussian-type orbital
+/// $\chi(\mathbf{r}) = P(\mathbf{r} - \mathbf{R}) \sum_k d_k N_k e^{-\alpha_k r^2}$,
+/// centered at $\mathbf{R}$, with $r = |\mathbf{r} - \mathbf{R}|$. Each primitive
+/// $N_k r^l e^{-\alpha_k r^2}$ is normalized, and the contraction coefficients
+/// $d_k$ are applied as given.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GaussianOrbital {
+    center: Array1<f64>,
+    exponents: Array1<f64>,
+    coefficients: Array1<f64>,
+    angular: AngularPart,
+}
+
+impl GaussianOrbital {
+    pub fn new(
+        center: Array1<f64>,
+        exponents: Array1<f64>,
+        coefficients: Array1<f64>,
+        angular: AngularPart,
+    ) -> Self {
+        assert_eq!(exponents.len(), coefficients.len());
+        Self {
+            center,
+            exponents,
+            coefficients,
+            angular,
+        }
+    }
+
+    /// Radial normalization of a primitive with exponent $\alpha$,
+    /// $N = \sqrt{2^{l + 2} (2\alpha)^{l + 3/2} / ((2l + 1)!! \sqrt{\pi})}$.
+    fn normalization(&self, alpha: f64) -> f64 {
+        let l = self.angular.degree();
+        f64::sqrt(
+            2f64.powi(l + 2) * (2.0 * alpha).powf(f64::from(l) + 1.5)
+                / (double_factorial(2 * l + 1) * std::f64::consts::PI.sqrt()),
+        )
+    }
+
+    /// Contribution of each primitive to the radial function, $d_k N_k e^{-\alpha_k r^2}$.
+    fn primitives(&self, r: f64) -> Array1<f64> {
+        let mut prims = Array1::<f64>::zeros(self.exponents.len());
+        for ((p, &alpha), d) in prims
+            .iter_mut()
+            .zip(self.exponents.iter())
+            .zip(self.coefficients.iter())
+        {
+            *p = d * self.normalization(alpha) * f64::exp(-alpha * r.powi(2));
+        }
+        prims
+    }
+
+    /// Radial function $R$, $R'/r$ and $R''$.
+    fn radial(&self, r: f64) -> (f64, f64, f64) {
+        let prims = self.primitives(r);
+        let rad = prims.sum();
+        let drad_r = -2.0 * prims.dot(&self.exponents);
+        let d2rad = prims
+            .iter()
+            .zip(self.exponents.iter())
+            .map(|(p, alpha)| p * (4.0 * alpha.powi(2) * r.powi(2) - 2.0 * alpha))
+            .sum();
+        (rad, drad_r, d2rad)
+    }
+
+    /// Derivatives of the orbital value with respect to each primitive exponent $\alpha_k$.
+    pub fn exponent_derivatives(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        let x = pos - &self.center;
+        let r = x.norm_l2();
+        let (poly, _, _) = self.angular.derivatives(&x);
+        let l = f64::from(self.angular.degree());
+        let mut derivs = self.primitives(r) * poly;
+        for (d, alpha) in derivs.iter_mut().zip(self.exponents.iter()) {
+            *d *= (2.0 * l + 3.0) / (4.0 * alpha) - r.powi(2);
+        }
+        Ok(derivs)
+    }
+
+    pub fn exponents(&self) -> &Array1<f64> {
+        &self.exponents
+    }
+
+    pub fn coefficients(&self) -> &Array1<f64> {
+        &self.coefficients
+    }
+
+    pub fn center(&self) -> &Array1<f64> {
+        &self.center
+    }
+
+    pub fn angular(&self) -> &AngularPart {
+        &self.angular
+    }
+}
+
+impl Function<f64> for GaussianOrbital {
+    type D = Ix1;
+
+    fn value(&self, pos: &Array1<f64>) -> Result<f64> {
+        let x = pos - &self.center;
+        let (rad, _, _) = self.radial(x.norm_l2());
+        Ok(rad * self.angular.derivatives(&x).0)
+    }
+}
+
+impl Differentiate for GaussianOrbital {
+    type D = Ix1;
+
+    fn gradient(&self, pos: &Array1<f64>) -> Result<Array1<f64>> {
+        Ok(self.vgl(pos)?.1)
+    }
+
+    fn laplacian(&self, pos: &Array1<f64>) -> Result<f64> {
+        Ok(self.vgl(pos)?.2)
+    }
+
+    fn vgl(&self, pos: &Array1<f64>) -> Result<Vgl<Ix1>> {
+        let x = pos - &self.center;
+        Ok(self.angular.combine(&x, self.radial(x.norm_l2())))
+    }
+}
+
+impl Hessian for GaussianOrbital {
+    fn hessian(&self, pos: &Array1<f64>) -> Result<Array2<f64>> {
+        let x = pos - &self.center;
+        Ok(self.angular.combine_hessian(&x, self.radial(x.norm_l2())))
+    }
+}
+
+impl Validate for AngularPart {}
+
+impl Validate for SlaterOrbital {
+    fn validate(&self) -> Result<()> {
+        check_invariant(self.n >= 1, "the principal quantum number must be positive")
+    }
+}
+
+impl Validate for GaussianOrbital {
+    fn validate(&self) -> Result<()> {
+        check_invariant(
+            !self.exponents.is_empty() && self.exponents.len() == self.coefficients.len(),
+            "a contracted Gaussian needs one coefficient per exponent",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    const H: f64 = 1e-4;
+
+    fn check_derivatives<T>(orbital: &T, pos: &Array1<f64>)
+    where
+        T: Function<f64, D = Ix1> + Differentiate<D = Ix1>,
+    {
+        let value = orbital.value(pos).unwrap();
+        let grad = orbital.gradient(pos).unwrap();
+        let mut lapl = 0.0;
+        for k in 0..3 {
+            let mut step = Array1::zeros(3);
+            step[k] = H;
+            let forward = orbital.value(&(pos + &step)).unwrap();
+            let backward = orbital.value(&(pos - &step)).unwrap();
+            assert!((grad[k] - (forward - backward) / (2.0 * H)).abs() < 1e-6);
+            lapl += (forward - 2.0 * value + backward) / H.powi(2);
+        }
+        assert!((orbital.laplacian(pos).unwrap() - lapl).abs() < 1e-4);
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let center = array![0.2, -0.1, 0.3];
+        let pos = array![0.7, 0.4, -0.2];
+        for l in 0..=3 {
+            for angular in AngularPart::spherical_shell(l)
+                .into_iter()
+                .chain(AngularPart::cartesian_shell(l))
+            {
+                let sto = SlaterOrbital::new(center.clone(), l + 2, 1.3, angular.clone());
+                check_derivatives(&sto, &pos);
+                let gto = GaussianOrbital::new(
+                    center.clone(),
+                    array![2.1, 0.6],
+                    array![0.4, 0.7],
+                    angular,
+                );
+                check_derivatives(&gto, &pos);
+            }
+        }
+    }
+
+    fn check_hessian<T>(orbital: &T, pos: &Array1<f64>)
+    where
+        T: Function<f64, D = Ix1> + Hessian,
+    {
+        let hessian = orbital.hessian(pos).unwrap();
+        for k in 0..3 {
+            let mut step = Array1::zeros(3);
+            step[k] = H;
+            let fd = (orbital.gradient(&(pos + &step)).unwrap()
+                - orbital.gradient(&(pos - &step)).unwrap())
+                / (2.0 * H);
+            assert!(hessian.row(k).all_close(&fd, 1e-6));
+        }
+        let trace: f64 = hessian.diag().sum();
+        assert!((trace - orbital.laplacian(pos).unwrap()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn hessian_matches_finite_differences() {
+        let center = array![0.2, -0.1, 0.3];
+        let pos = array![0.7, 0.4, -0.2];
+        for l in 0..=3 {
+            for angular in AngularPart::cartesian_shell(l) {
+                let sto = SlaterOrbital::new(center.clone(), l + 2, 1.3, angular.clone());
+                check_hessian(&sto, &pos);
+                let gto = GaussianOrbital::new(
+                    center.clone(),
+                    array![2.1, 0.6],
+                    array![0.4, 0.7],
+                    angular,
+                );
+                check_hessian(&gto, &pos);
+            }
+        }
+    }
+
+    #[test]
+    fn exponent_derivatives_match_finite_differences() {
+        let center = array![0.2, -0.1, 0.3];
+        let pos = array![0.7, 0.4, -0.2];
+        let angular = AngularPart::spherical(2, 1);
+
+        let sto = |zeta| SlaterOrbital::new(center.clone(), 3, zeta, angular.clone());
+        let fd =
+            (sto(1.3 + H).value(&pos).unwrap() - sto(1.3 - H).value(&pos).unwrap()) / (2.0 * H);
+        assert!((sto(1.3).exponent_derivative(&pos).unwrap() - fd).abs() < 1e-6);
+
+        let gto = |exponents| {
+            GaussianOrbital::new(center.clone(), exponents, array![0.4, 0.7], angular.clone())
+        };
+        let derivs = gto(array![2.1, 0.6]).exponent_derivatives(&pos).unwrap();
+        for (k, step) in [array![H, 0.0], array![0.0, H]].iter().enumerate() {
+            let forward = gto(array![2.1, 0.6] + step).value(&pos).unwrap();
+            let backward = gto(array![2.1, 0.6] - step).value(&pos).unwrap();
+            assert!((derivs[k] - (forward - backward) / (2.0 * H)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn orbitals_are_normalized() {
+        // midpoint rule on a cube large enough to contain the orbital
+        let h = 0.12;
+        let npts = 60;
+        let orbitals = vec![
+            GaussianOrbital::new(
+                array![0.0, 0.0, 0.0],
+                array![1.2],
+                array![1.0],
+                AngularPart::spherical(2, 0),
+            ),
+            GaussianOrbital::new(
+                array![0.0, 0.0, 0.0],
+                array![0.9],
+                array![1.0],
+                AngularPart::cartesian([1, 1, 0]),
+            ),
+        ];
+        for orbital in orbitals {
+            let mut norm = 0.0;
+            for i in 0..npts {
+                for j in 0..npts {
+                    for k in 0..npts {
+                        let pos = array![i as f64, j as f64, k as f64] * h
+                            - 0.5 * h * (npts as f64 - 1.0);
+                        norm += orbital.value(&pos).unwrap().powi(2) * h.powi(3);
+                    }
+                }
+            }
+            assert!((norm - 1.0).abs() < 1e-4);
+        }
+    }
+}
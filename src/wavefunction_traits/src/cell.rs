@@ -0,0 +1,239 @@
+// Standard imports
+use std::convert::TryFrom;
+use std::f64::consts::PI;
+// Third party imports
+use ndarray::{Array1, Array2, Axis};
+use ndarray_linalg::{Determinant, Inverse, Norm};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::{check_input, Validate};
+use errors::Error;
+
+/// Periodic simulation cell, spanned by the lattice vectors $\mathbf{a}_i$
+/// in the rows of `lattice`. The reciprocal lattice vectors $\mathbf{b}_j$
+/// satisfy $\mathbf{a}_i \cdot \mathbf{b}_j = 2\pi\delta_{ij}$.
+/// Only the lattice is serialized; all other quantities are rebuilt from it
+/// when loading, so that they cannot contradict an edited lattice.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "Lattice", into = "Lattice")]
+pub struct SimulationCell {
+    lattice: Array2<f64>,
+    inverse: Array2<f64>,
+    reciprocal: Array2<f64>,
+    volume: f64,
+    orthogonal: bool,
+}
+
+/// Serialized form of a `SimulationCell`.
+#[derive(Serialize, Deserialize)]
+struct Lattice {
+    lattice: Array2<f64>,
+}
+
+impl From<SimulationCell> for Lattice {
+    fn from(cell: SimulationCell) -> Self {
+        Self {
+            lattice: cell.lattice,
+        }
+    }
+}
+
+impl TryFrom<Lattice> for SimulationCell {
+    type Error = String;
+
+    fn try_from(lattice: Lattice) -> Result<Self, String> {
+        Self::new(lattice.lattice).map_err(|e| format!("{:?}", e))
+    }
+}
+
+impl SimulationCell {
+    pub fn new(lattice: Array2<f64>) -> Result<Self, Error> {
+        check_input(
+            lattice.is_square() && !lattice.is_empty(),
+            "the lattice must be a square matrix",
+        )?;
+        let volume = lattice.det()?.abs();
+        check_input(
+            volume.is_normal(),
+            "the lattice vectors must be linearly independent",
+        )?;
+        let inverse = lattice.inv()?;
+        let reciprocal = inverse.t().to_owned() * (2.0 * PI);
+        let overlaps = lattice.dot(&lattice.t());
+        let orthogonal = overlaps
+            .indexed_iter()
+            .all(|((i, j), overlap)| i == j || overlap.abs() < 1e-12 * volume);
+        Ok(Self {
+            lattice,
+            inverse,
+            reciprocal,
+            volume,
+            orthogonal,
+        })
+    }
+
+    /// Cubic cell of side `side` in three dimensions.
+    pub fn cubic(side: f64) -> Result<Self, Error> {
+        Self::new(Array2::eye(3) * side)
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.lattice.len_of(Axis(0))
+    }
+
+    pub fn lattice(&self) -> &Array2<f64> {
+        &self.lattice
+    }
+
+    pub fn reciprocal(&self) -> &Array2<f64> {
+        &self.reciprocal
+    }
+
+    /// Volume of the cell, or its area in two dimensions.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    /// Coordinates of `pos` in the basis of lattice vectors.
+    pub fn fractional(&self, pos: &Array1<f64>) -> Array1<f64> {
+        pos.dot(&self.inverse)
+    }
+
+    /// Image of `pos` inside the cell, i.e. with fractional coordinates in $[0, 1)$.
+    pub fn wrap(&self, pos: &Array1<f64>) -> Array1<f64> {
+        self.fractional(pos)
+            .mapv(|f| f - f.floor())
+            .dot(&self.lattice)
+    }
+
+    /// Wrap every electron of a configuration into the cell.
+    pub fn wrap_configuration(&self, cfg: &Array2<f64>) -> Array2<f64> {
+        let mut wrapped = cfg.clone();
+        for mut pos in wrapped.outer_iter_mut() {
+            let image = self.wrap(&pos.to_owned());
+            pos.assign(&image);
+        }
+        wrapped
+    }
+
+    /// Shortest periodic image of the separation vector `r`. For cells that
+    /// are not orthogonal, rounding the fractional coordinates need not give
+    /// the shortest image, so the neighbouring images are searched as well.
+    pub fn minimum_image(&self, r: &Array1<f64>) -> Array1<f64> {
+        let rounded = self
+            .fractional(r)
+            .mapv(|f| f - f.round())
+            .dot(&self.lattice);
+        if self.orthogonal {
+            return rounded;
+        }
+        let mut shortest = rounded.clone();
+        let mut shortest_norm = rounded.norm_l2();
+        let dim = self.dimension();
+        for neighbour in 0..3_usize.pow(dim as u32) {
+            let shift = (0..dim)
+                .map(|i| (neighbour / 3_usize.pow(i as u32) % 3) as f64 - 1.0)
+                .collect::<Array1<f64>>();
+            let image = &rounded + &shift.dot(&self.lattice);
+            let norm = image.norm_l2();
+            if norm < shortest_norm {
+                shortest_norm = norm;
+                shortest = image;
+            }
+        }
+        shortest
+    }
+
+    /// Lattice vectors $\mathbf{L}$ with $|\mathbf{L}| \leq$ `cutoff`,
+    /// including the origin, in order of increasing length.
+    pub fn lattice_points(&self, cutoff: f64) -> Vec<Array1<f64>> {
+        points_within(&self.lattice, &self.reciprocal, cutoff)
+    }
+
+    /// Reciprocal lattice vectors $\mathbf{G}$ with $|\mathbf{G}| \leq$
+    /// `cutoff`, including the origin, in order of increasing length.
+    pub fn reciprocal_points(&self, cutoff: f64) -> Vec<Array1<f64>> {
+        points_within(&self.reciprocal, &self.lattice, cutoff)
+    }
+}
+
+/// Integer combinations of the rows of `basis` within a sphere of radius
+/// `cutoff`; `dual` is the basis of the dual lattice, which bounds the
+/// coefficients by $|n_i| \leq r_c |\mathbf{d}_i| / 2\pi$.
+fn points_within(basis: &Array2<f64>, dual: &Array2<f64>, cutoff: f64) -> Vec<Array1<f64>> {
+    let bounds: Vec<i64> = dual
+        .outer_iter()
+        .map(|d| (cutoff * d.dot(&d).sqrt() / (2.0 * PI)).ceil() as i64)
+        .collect();
+    let mut coefficients: Vec<i64> = bounds.iter().map(|b| -b).collect();
+    let mut points = Vec::new();
+    loop {
+        let n = coefficients
+            .iter()
+            .map(|&c| c as f64)
+            .collect::<Array1<f64>>();
+        let point = n.dot(basis);
+        if point.norm_l2() <= cutoff {
+            points.push(point);
+        }
+        // advance the coefficients like an odometer
+        let mut i = 0;
+        while i < bounds.len() && coefficients[i] == bounds[i] {
+            coefficients[i] = -bounds[i];
+            i += 1;
+        }
+        if i == bounds.len() {
+            break;
+        }
+        coefficients[i] += 1;
+    }
+    points.sort_by(|a, b| a.norm_l2().partial_cmp(&b.norm_l2()).unwrap());
+    points
+}
+
+// the lattice is checked by `SimulationCell::new` on deserialization
+impl Validate for SimulationCell {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn wrap_and_minimum_image() {
+        let cell = SimulationCell::cubic(2.0).unwrap();
+        assert!((cell.volume() - 8.0).abs() < 1e-12);
+        let wrapped = cell.wrap(&array![2.5, -0.5, 7.0]);
+        assert!((wrapped - array![0.5, 1.5, 1.0]).norm_l2() < 1e-12);
+        let image = cell.minimum_image(&array![1.9, -1.2, 0.3]);
+        assert!((image - array![-0.1, 0.8, 0.3]).norm_l2() < 1e-12);
+
+        // strongly sheared two-dimensional cell, where rounding alone fails
+        let cell = SimulationCell::new(array![[1.0, 0.0], [0.9, 0.3]]).unwrap();
+        let r = array![0.45, 0.15];
+        let image = cell.minimum_image(&r);
+        let shortest = cell
+            .lattice_points(3.0)
+            .iter()
+            .map(|l| (&r + l).norm_l2())
+            .fold(f64::INFINITY, f64::min);
+        assert!((image.norm_l2() - shortest).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reciprocal_lattice() {
+        let cell =
+            SimulationCell::new(array![[0.0, 1.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 0.0]]).unwrap();
+        let products = cell.lattice().dot(&cell.reciprocal().t());
+        assert!((products - Array2::<f64>::eye(3) * 2.0 * PI).norm_l2() < 1e-12);
+        // the fcc lattice has 12 nearest neighbours
+        assert_eq!(cell.lattice_points(2_f64.sqrt() + 1e-8).len(), 13);
+    }
+
+    #[test]
+    fn invalid_lattices() {
+        assert!(SimulationCell::new(array![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]).is_err());
+        assert!(SimulationCell::new(array![[1.0, 2.0], [2.0, 4.0]]).is_err());
+        assert!(SimulationCell::cubic(0.0).is_err());
+    }
+}
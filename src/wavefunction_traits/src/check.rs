@@ -0,0 +1,190 @@
+// Third party imports
+use ndarray::{Array1, Array2, Axis, Ix2};
+#[cfg(any(test, feature = "proptest"))]
+use proptest::prelude::*;
+// First party imports
+use crate::{Differentiate, Function, NumericalDerivatives, Optimize, Stencil};
+use errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Discrepancies between analytic and finite difference derivatives of a
+/// wave function $\psi$ at a single configuration. All discrepancies are
+/// relative to $|\psi|$, so they directly measure errors in local quantities
+/// such as $\nabla\psi/\psi$.
+#[derive(Clone, Debug)]
+pub struct DerivativeCheck {
+    /// Norm of the gradient discrepancy for each electron.
+    pub gradient: Array1<f64>,
+    /// Discrepancy of the laplacian.
+    pub laplacian: f64,
+    /// Discrepancy of the parameter gradient for each parameter;
+    /// empty if parameters were not checked.
+    pub parameter_gradient: Array1<f64>,
+}
+
+impl DerivativeCheck {
+    fn discrepancies(&self) -> impl Iterator<Item = f64> + '_ {
+        self.gradient
+            .iter()
+            .chain(self.parameter_gradient.iter())
+            .chain(std::iter::once(&self.laplacian))
+            .cloned()
+    }
+
+    /// The largest discrepancy of any derivative, or NaN if any derivative
+    /// is NaN.
+    pub fn max_discrepancy(&self) -> f64 {
+        self.discrepancies()
+            .fold(0.0, |max, d| if max.is_nan() || d <= max { max } else { d })
+    }
+
+    /// Whether all discrepancies are below the given tolerance; never true
+    /// if any of them is NaN.
+    pub fn passes(&self, tolerance: f64) -> bool {
+        self.discrepancies().all(|d| d < tolerance)
+    }
+}
+
+/// Compare the `gradient` and `laplacian` of a wave function to fourth order
+/// finite differences with step `step` at configuration `cfg`.
+pub fn check_derivatives<T>(wf: &T, cfg: &Array2<f64>, step: f64) -> Result<DerivativeCheck>
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2> + Clone,
+{
+    let numerical = NumericalDerivatives::new(wf.clone(), step, Stencil::FourthOrder);
+    let value = wf.value(cfg)?.abs();
+    let gradient_error = wf.gradient(cfg)? - numerical.gradient(cfg)?;
+    let gradient = gradient_error
+        .map_axis(Axis(1), |row| row.dot(&row).sqrt())
+        .mapv(|d| d / value);
+    let laplacian = (wf.laplacian(cfg)? - numerical.laplacian(cfg)?).abs() / value;
+    Ok(DerivativeCheck {
+        gradient,
+        laplacian,
+        parameter_gradient: Array1::zeros(0),
+    })
+}
+
+/// Compare the `gradient`, `laplacian` and `parameter_gradient` of a wave function
+/// to fourth order finite differences with step `step` at configuration `cfg`.
+pub fn check_parameter_derivatives<T>(
+    wf: &T,
+    cfg: &Array2<f64>,
+    step: f64,
+) -> Result<DerivativeCheck>
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2> + Optimize + Clone,
+{
+    let numerical = NumericalDerivatives::new(wf.clone(), step, Stencil::FourthOrder);
+    let value = wf.value(cfg)?.abs();
+    let parameter_gradient = (wf.parameter_gradient(cfg)? - numerical.parameter_gradient(cfg)?)
+        .mapv(|d| d.abs() / value);
+    Ok(DerivativeCheck {
+        parameter_gradient,
+        ..check_derivatives(wf, cfg, step)?
+    })
+}
+
+/// Proptest strategy generating configurations of `num_electrons` electrons in
+/// `dimension` dimensions, with coordinates uniformly distributed in $[-L, L)$.
+#[cfg(any(test, feature = "proptest"))]
+pub fn configurations(
+    num_electrons: usize,
+    dimension: usize,
+    extent: f64,
+) -> impl Strategy<Value = Array2<f64>> {
+    proptest::collection::vec(-extent..extent, dimension * num_electrons)
+        .prop_map(move |v| Array2::from_shape_vec((num_electrons, dimension), v).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Gaussian;
+    use ndarray::array;
+
+    /// Gaussian with a deliberately wrong laplacian.
+    #[derive(Clone)]
+    struct BrokenGaussian(Gaussian);
+
+    impl Function<f64> for BrokenGaussian {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+            self.0.value(cfg)
+        }
+    }
+
+    impl Differentiate for BrokenGaussian {
+        type D = Ix2;
+
+        fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+            self.0.gradient(cfg)
+        }
+
+        fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+            Ok(1.1 * self.0.laplacian(cfg)?)
+        }
+    }
+
+    /// Gaussian whose gradient is NaN.
+    #[derive(Clone)]
+    struct NanGaussian(Gaussian);
+
+    impl Function<f64> for NanGaussian {
+        type D = Ix2;
+
+        fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+            self.0.value(cfg)
+        }
+    }
+
+    impl Differentiate for NanGaussian {
+        type D = Ix2;
+
+        fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+            Ok(self.0.gradient(cfg)?.mapv(|_| f64::NAN))
+        }
+
+        fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+            self.0.laplacian(cfg)
+        }
+    }
+
+    #[test]
+    fn detects_wrong_laplacian() {
+        let wf = BrokenGaussian(Gaussian::new(0.8, array![[0.0, 0.0, 0.0]]));
+        let check = check_derivatives(&wf, &array![[0.3, -0.2, 0.5]], 1e-3).unwrap();
+        assert!(check.gradient[0] < 1e-8);
+        assert!(!check.passes(1e-6));
+    }
+
+    #[test]
+    fn detects_nan_gradient() {
+        let wf = NanGaussian(Gaussian::new(0.8, array![[0.0, 0.0, 0.0]]));
+        let check = check_derivatives(&wf, &array![[0.3, -0.2, 0.5]], 1e-3).unwrap();
+        assert!(check.laplacian < 1e-6);
+        assert!(check.max_discrepancy().is_nan());
+        assert!(!check.passes(1e-6));
+    }
+
+    proptest! {
+        #[test]
+        fn gaussian_derivatives_are_consistent(cfg in configurations(2, 3, 1.5)) {
+            let wf = Gaussian::new(0.7, array![[0.1, 0.0, -0.2], [0.0, 0.3, 0.0]]);
+            let check = check_parameter_derivatives(&wf, &cfg, 1e-3).unwrap();
+            prop_assert_eq!(check.gradient.len(), 2);
+            prop_assert_eq!(check.parameter_gradient.len(), 1);
+            prop_assert!(check.passes(1e-6));
+        }
+
+        #[test]
+        fn gaussian_derivatives_are_consistent_in_2d(cfg in configurations(3, 2, 1.5)) {
+            let wf = Gaussian::new(0.7, array![[0.1, 0.0], [0.0, 0.3], [-0.2, 0.1]]);
+            let check = check_parameter_derivatives(&wf, &cfg, 1e-3).unwrap();
+            prop_assert_eq!(check.gradient.len(), 3);
+            prop_assert!(check.passes(1e-6));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Third party imports
+use ndarray::{Array1, Array2, Ix2};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::{
+    check_invariant, Differentiate, Function, LogVgl, Optimize, Validate, Vgl, WaveFunction,
+};
+use errors::Error::{self, ParameterAccessError};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Adapter that keeps selected variational parameters of a wave function fixed
+/// during optimization, by clearing their entries in `parameter_mask`. The
+/// wave function is otherwise unchanged, e.g. the Slater part of a
+/// Slater-Jastrow wave function can be frozen while its Jastrow factor is
+/// optimized.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrozenParameters<T> {
+    wave_function: T,
+    mask: Array1<bool>,
+}
+
+impl<T: Optimize> FrozenParameters<T> {
+    pub fn new(wave_function: T) -> Self {
+        let mask = wave_function.parameter_mask();
+        Self {
+            wave_function,
+            mask,
+        }
+    }
+
+    /// Freeze the parameter called `name` in group `group`.
+    pub fn freeze(self, group: &str, name: &str) -> Result<Self> {
+        let groups = self.wave_function.parameter_groups();
+        let names = self.wave_function.parameter_names();
+        self.freeze_where(|p| groups[p] == group && names[p] == name)
+            .ok_or_else(|| ParameterAccessError(format!("{} in group {}", name, group)))
+    }
+
+    /// Freeze all parameters in group `group`.
+    pub fn freeze_group(self, group: &str) -> Result<Self> {
+        let groups = self.wave_function.parameter_groups();
+        self.freeze_where(|p| groups[p] == group)
+            .ok_or_else(|| ParameterAccessError(format!("group {}", group)))
+    }
+
+    // None if no parameter matched
+    fn freeze_where<F: Fn(usize) -> bool>(mut self, frozen: F) -> Option<Self> {
+        let mut found = false;
+        for (p, free) in self.mask.iter_mut().enumerate() {
+            if frozen(p) {
+                *free = false;
+                found = true;
+            }
+        }
+        if found {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.wave_function
+    }
+}
+
+impl<T> Function<f64> for FrozenParameters<T>
+where
+    T: Function<f64, D = Ix2>,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        self.wave_function.value(cfg)
+    }
+
+    fn log_value(&self, cfg: &Array2<f64>) -> Result<(f64, f64)> {
+        self.wave_function.log_value(cfg)
+    }
+}
+
+impl<T> Differentiate for FrozenParameters<T>
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2>,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        self.wave_function.gradient(cfg)
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        self.wave_function.laplacian(cfg)
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        self.wave_function.vgl(cfg)
+    }
+
+    fn log_vgl(&self, cfg: &Array2<f64>) -> Result<LogVgl> {
+        self.wave_function.log_vgl(cfg)
+    }
+}
+
+impl<T> WaveFunction for FrozenParameters<T>
+where
+    T: WaveFunction + Function<f64, D = Ix2> + Differentiate<D = Ix2>,
+{
+    fn num_electrons(&self) -> usize {
+        self.wave_function.num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.wave_function.num_up()
+    }
+
+    fn dimension(&self) -> usize {
+        self.wave_function.dimension()
+    }
+
+    fn ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<f64> {
+        self.wave_function.ratio(cfg, pos, idx)
+    }
+
+    fn gradient_ratio(
+        &self,
+        cfg: &Array2<f64>,
+        pos: &Array1<f64>,
+        idx: usize,
+    ) -> Result<Array1<f64>> {
+        self.wave_function.gradient_ratio(cfg, pos, idx)
+    }
+
+    fn refresh(&mut self, cfg: &Array2<f64>) -> Result<()> {
+        self.wave_function.refresh(cfg)
+    }
+
+    fn accept_move(&mut self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<()> {
+        self.wave_function.accept_move(cfg, pos, idx)
+    }
+
+    fn reject_move(&mut self, idx: usize) {
+        self.wave_function.reject_move(idx)
+    }
+}
+
+impl<T> Optimize for FrozenParameters<T>
+where
+    T: Function<f64, D = Ix2> + Optimize,
+{
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        self.wave_function.parameter_gradient(cfg)
+    }
+
+    fn log_parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        self.wave_function.log_parameter_gradient(cfg)
+    }
+
+    /// Updates only the free parameters; the entries of `deltap` for frozen
+    /// parameters are ignored.
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        let free = self.mask.mapv(|free| if free { 1.0 } else { 0.0 });
+        self.wave_function.update_parameters(&(deltap * &free));
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.wave_function.parameters()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.wave_function.num_parameters()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        self.wave_function.parameter_names()
+    }
+
+    fn parameter_groups(&self) -> Vec<String> {
+        self.wave_function.parameter_groups()
+    }
+
+    fn parameter_mask(&self) -> Array1<bool> {
+        self.mask.clone()
+    }
+}
+
+impl<T: Validate + Optimize> Validate for FrozenParameters<T> {
+    fn validate(&self) -> Result<()> {
+        self.wave_function.validate()?;
+        check_invariant(
+            self.mask.len() == self.wave_function.num_parameters(),
+            "the parameter mask must have one entry per parameter",
+        )
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        self.wave_function.num_particles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Gaussian;
+    use crate::{LinearCombination, Product};
+    use ndarray::array;
+
+    #[test]
+    fn freezes_by_group_and_name() {
+        let phi = Gaussian::new(0.8, array![[0.5, 0.0, 0.0]]);
+        let wf = Product::new(
+            phi.clone(),
+            LinearCombination::new(array![1.0, 0.5], vec![phi.clone(), phi]).unwrap(),
+        );
+        assert_eq!(wf.parameter_names(), vec!["p_1", "c_1", "c_2"]);
+        assert_eq!(
+            wf.parameter_groups(),
+            vec!["parameters", "linear combination", "linear combination"]
+        );
+
+        let frozen = FrozenParameters::new(wf.clone())
+            .freeze("linear combination", "c_2")
+            .unwrap();
+        assert_eq!(frozen.parameter_mask(), array![true, true, false]);
+        let mut frozen = FrozenParameters::new(wf.clone())
+            .freeze_group("linear combination")
+            .unwrap();
+        assert_eq!(frozen.parameter_mask(), array![true, false, false]);
+
+        // updates leave the frozen parameters unchanged
+        frozen.update_parameters(&array![0.1, 0.2, 0.3]);
+        assert_eq!(
+            frozen.parameters(),
+            &wf.parameters() + &array![0.1, 0.0, 0.0]
+        );
+
+        assert!(FrozenParameters::new(wf.clone())
+            .freeze("parameters", "c_1")
+            .is_err());
+        assert!(FrozenParameters::new(wf).freeze_group("jastrow").is_err());
+    }
+}
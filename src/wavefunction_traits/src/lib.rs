@@ -1,24 +1,215 @@
 use errors::Error;
-use ndarray::Array;
+use ndarray::{Array, Array1, Array2, Dimension, Ix1, Ix2, ScalarOperand};
+use ndarray_linalg::Scalar;
+
+mod cell;
+mod check;
+mod frozen;
+mod linear_combination;
+mod numerical;
+mod persist;
+mod product;
+#[cfg(test)]
+mod testing;
+
+pub use crate::cell::*;
+pub use crate::check::*;
+pub use crate::frozen::*;
+pub use crate::linear_combination::*;
+pub use crate::numerical::*;
+pub use crate::persist::*;
+pub use crate::product::*;
 
 type Result<T> = std::result::Result<T, Error>;
 
-/// Interface for dealing with functions f: F^n -> F, where F is any field.
+/// Field over which functions take their values: `f64` or `c64`.
+pub trait Field: Scalar<Real = f64> + ScalarOperand {}
+
+impl<T: Scalar<Real = f64> + ScalarOperand> Field for T {}
+
+/// Phase $f/|f|$ of a value, taken to be unity at zero.
+fn phase<T: Field>(value: T) -> T {
+    let norm = value.abs();
+    if norm == 0.0 {
+        T::one()
+    } else {
+        value.div_real(norm)
+    }
+}
+
+/// Interface for dealing with functions f: R^n -> F, where F is any field.
 pub trait Function<T> {
     type D;
 
-    fn value(&self, cfg: &Array<T, Self::D>) -> Result<T>;
+    fn value(&self, cfg: &Array<f64, Self::D>) -> Result<T>;
+
+    /// Logarithm of the absolute value $\ln|f|$, and the phase $f/|f|$, which
+    /// for real functions is the sign of $f$.
+    /// Functions whose value may under- or overflow should override this.
+    fn log_value(&self, cfg: &Array<f64, Self::D>) -> Result<(f64, T)>
+    where
+        T: Field,
+    {
+        let value = self.value(cfg)?;
+        Ok((value.abs().ln(), phase(value)))
+    }
 }
 
+/// Value, gradient and laplacian of a function, evaluated together.
+pub type Vgl<D = Ix2, T = f64> = (T, Array<T, D>, T);
+
+/// Logarithm $\ln|f|$, phase, gradient $\nabla \ln f$ and laplacian
+/// $\nabla^2 \ln f$ of a function, evaluated together.
+pub type LogVgl<D = Ix2, T = f64> = (f64, T, Array<T, D>, T);
+
 /// Interface for creating once- and twice differentiable functions.
-pub trait Differentiate {
+pub trait Differentiate<T = f64> {
     type D;
 
-    fn gradient(&self, cfg: &Array<f64, Self::D>) -> Result<Array<f64, Self::D>>;
+    fn gradient(&self, cfg: &Array<f64, Self::D>) -> Result<Array<T, Self::D>>;
+
+    fn laplacian(&self, cfg: &Array<f64, Self::D>) -> Result<T>;
 
-    fn laplacian(&self, cfg: &Array<f64, Self::D>) -> Result<f64>;
+    /// Value, gradient and laplacian in a single call. Implementors that share
+    /// intermediate results between the three should override this.
+    fn vgl(
+        &self,
+        cfg: &Array<f64, <Self as Differentiate<T>>::D>,
+    ) -> Result<Vgl<<Self as Differentiate<T>>::D, T>>
+    where
+        Self: Function<T, D = <Self as Differentiate<T>>::D>,
+    {
+        Ok((self.value(cfg)?, self.gradient(cfg)?, self.laplacian(cfg)?))
+    }
+
+    /// Log-domain counterpart of `vgl`. The default divides out the value, using
+    /// $\nabla^2 \ln f = \nabla^2 f / f - (\nabla f / f)^2$.
+    fn log_vgl(
+        &self,
+        cfg: &Array<f64, <Self as Differentiate<T>>::D>,
+    ) -> Result<LogVgl<<Self as Differentiate<T>>::D, T>>
+    where
+        Self: Function<T, D = <Self as Differentiate<T>>::D>,
+        <Self as Differentiate<T>>::D: Dimension,
+        T: Field,
+    {
+        let (value, grad, lapl) = self.vgl(cfg)?;
+        let grad = grad / value;
+        let grad_squared = (&grad * &grad).sum();
+        Ok((
+            value.abs().ln(),
+            phase(value),
+            grad,
+            lapl / value - grad_squared,
+        ))
+    }
 }
 
-pub trait WaveFunction {
+/// Interface for single-particle functions with a known Hessian matrix
+/// $\partial_\alpha \partial_\beta f$, as needed when such functions are
+/// evaluated at transformed coordinates.
+pub trait Hessian: Differentiate<D = Ix1> {
+    fn hessian(&self, pos: &Array1<f64>) -> Result<Array2<f64>>;
+}
+
+/// Interface for many-electron wave functions taking values in the field `T`.
+pub trait WaveFunction<T = f64> {
     fn num_electrons(&self) -> usize;
+
+    /// Number of spatial dimensions in which the electrons move, i.e. the
+    /// number of columns of a configuration.
+    fn dimension(&self) -> usize {
+        3
+    }
+
+    /// Number of spin-up electrons. Electrons are ordered such that the
+    /// first `num_up` rows of a configuration are spin-up, and the remaining
+    /// rows are spin-down. By default all electrons are taken to be spin-up.
+    fn num_up(&self) -> usize {
+        self.num_electrons()
+    }
+
+    /// Number of spin-down electrons.
+    fn num_down(&self) -> usize {
+        self.num_electrons() - self.num_up()
+    }
+
+    /// Ratio $\psi(R')/\psi(R)$, where $R'$ is obtained from the configuration
+    /// $R$ by moving electron `idx` to `pos`. Wave functions that cache
+    /// intermediate results may compute this without re-evaluating $\psi$.
+    fn ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<T>
+    where
+        Self: Function<T, D = Ix2>,
+        T: Field,
+    {
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(idx).assign(pos);
+        let (log_new, phase_new) = self.log_value(&cfg_new)?;
+        let (log_old, phase_old) = self.log_value(cfg)?;
+        Ok((phase_new / phase_old).mul_real((log_new - log_old).exp()))
+    }
+
+    /// One-electron gradient $\nabla_i \psi(R') / \psi(R')$ with respect to
+    /// electron $i$ = `idx`, after moving it to `pos`.
+    fn gradient_ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<Array1<T>>
+    where
+        Self: Function<T, D = Ix2> + Differentiate<T, D = Ix2>,
+        T: Field,
+    {
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(idx).assign(pos);
+        Ok(self.log_vgl(&cfg_new)?.2.row(idx).to_owned())
+    }
+
+    /// Recompute any cached state for configuration `cfg`.
+    fn refresh(&mut self, _cfg: &Array2<f64>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Commit cached state after electron `idx` of configuration `cfg` was moved to `pos`.
+    fn accept_move(&mut self, _cfg: &Array2<f64>, _pos: &Array1<f64>, _idx: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// Roll back any cached state after a move of electron `idx` was rejected.
+    fn reject_move(&mut self, _idx: usize) {}
+}
+
+/// Interface for wave functions with variational parameters.
+pub trait Optimize {
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>>;
+
+    /// Logarithmic derivatives $\partial \ln|\psi| / \partial p_k$ with respect
+    /// to the variational parameters.
+    fn log_parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>>
+    where
+        Self: Function<f64, D = Ix2>,
+    {
+        Ok(self.parameter_gradient(cfg)? / self.value(cfg)?)
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>);
+
+    fn parameters(&self) -> Array1<f64>;
+
+    fn num_parameters(&self) -> usize;
+
+    /// Names of the variational parameters, in the order of `parameters`.
+    fn parameter_names(&self) -> Vec<String> {
+        (1..=self.num_parameters())
+            .map(|p| format!("p_{}", p))
+            .collect()
+    }
+
+    /// Group to which each parameter belongs, e.g. the factor of a product
+    /// wave function it parametrizes. Names are unique within a group.
+    fn parameter_groups(&self) -> Vec<String> {
+        vec![String::from("parameters"); self.num_parameters()]
+    }
+
+    /// Mask of the parameters that are varied by an `Optimizer`; frozen
+    /// parameters are `false`. See `FrozenParameters` to freeze parameters.
+    fn parameter_mask(&self) -> Array1<bool> {
+        Array1::from_elem(self.num_parameters(), true)
+    }
 }
@@ -0,0 +1,217 @@
+// Third party imports
+use ndarray::{Array, Array1, Array2, Dimension, Ix1, Ix2};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::{
+    check_input, check_invariant, Differentiate, Function, Hessian, Optimize, Validate, Vgl,
+    WaveFunction,
+};
+use errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+const COEFFICIENTS: &str = "a linear combination needs one coefficient per component";
+
+/// Linear combination $\psi = \sum_i c_i \phi_i$ of functions $\phi_i$ sharing
+/// the same domain, e.g. a bonding orbital $\phi_A + \phi_B$ or a
+/// multi-determinant expansion. The coefficients $c_i$ are the variational
+/// parameters.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LinearCombination<T> {
+    coefficients: Array1<f64>,
+    components: Vec<T>,
+}
+
+impl<T> LinearCombination<T> {
+    pub fn new(coefficients: Array1<f64>, components: Vec<T>) -> Result<Self> {
+        check_input(
+            !components.is_empty() && coefficients.len() == components.len(),
+            COEFFICIENTS,
+        )?;
+        Ok(Self {
+            coefficients,
+            components,
+        })
+    }
+
+    pub fn coefficients(&self) -> &Array1<f64> {
+        &self.coefficients
+    }
+
+    pub fn components(&self) -> &[T] {
+        &self.components
+    }
+}
+
+impl<T, D> Function<f64> for LinearCombination<T>
+where
+    T: Function<f64, D = D>,
+    D: Dimension,
+{
+    type D = D;
+
+    fn value(&self, cfg: &Array<f64, D>) -> Result<f64> {
+        let mut value = 0.0;
+        for (c, phi) in self.coefficients.iter().zip(self.components.iter()) {
+            value += c * phi.value(cfg)?;
+        }
+        Ok(value)
+    }
+}
+
+impl<T, D> Differentiate for LinearCombination<T>
+where
+    T: Function<f64, D = D> + Differentiate<D = D>,
+    D: Dimension,
+{
+    type D = D;
+
+    fn gradient(&self, cfg: &Array<f64, D>) -> Result<Array<f64, D>> {
+        let mut grad = Array::<f64, D>::zeros(cfg.raw_dim());
+        for (&c, phi) in self.coefficients.iter().zip(self.components.iter()) {
+            grad.scaled_add(c, &phi.gradient(cfg)?);
+        }
+        Ok(grad)
+    }
+
+    fn laplacian(&self, cfg: &Array<f64, D>) -> Result<f64> {
+        let mut lapl = 0.0;
+        for (c, phi) in self.coefficients.iter().zip(self.components.iter()) {
+            lapl += c * phi.laplacian(cfg)?;
+        }
+        Ok(lapl)
+    }
+
+    fn vgl(&self, cfg: &Array<f64, D>) -> Result<Vgl<D>> {
+        let mut value = 0.0;
+        let mut grad = Array::<f64, D>::zeros(cfg.raw_dim());
+        let mut lapl = 0.0;
+        for (&c, phi) in self.coefficients.iter().zip(self.components.iter()) {
+            let (value_phi, grad_phi, lapl_phi) = phi.vgl(cfg)?;
+            value += c * value_phi;
+            grad.scaled_add(c, &grad_phi);
+            lapl += c * lapl_phi;
+        }
+        Ok((value, grad, lapl))
+    }
+}
+
+impl<T> Hessian for LinearCombination<T>
+where
+    T: Function<f64, D = Ix1> + Hessian,
+{
+    fn hessian(&self, pos: &Array1<f64>) -> Result<Array2<f64>> {
+        let mut hessian = Array2::<f64>::zeros((pos.len(), pos.len()));
+        for (&c, phi) in self.coefficients.iter().zip(self.components.iter()) {
+            hessian.scaled_add(c, &phi.hessian(pos)?);
+        }
+        Ok(hessian)
+    }
+}
+
+impl<T: WaveFunction> WaveFunction for LinearCombination<T> {
+    fn num_electrons(&self) -> usize {
+        self.components[0].num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.components[0].num_up()
+    }
+
+    fn dimension(&self) -> usize {
+        self.components[0].dimension()
+    }
+}
+
+impl<T> Optimize for LinearCombination<T>
+where
+    T: Function<f64, D = Ix2>,
+{
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let values = self
+            .components
+            .iter()
+            .map(|phi| phi.value(cfg))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Array1::from_vec(values))
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.coefficients += deltap;
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.coefficients.clone()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        (1..=self.coefficients.len())
+            .map(|i| format!("c_{}", i))
+            .collect()
+    }
+
+    fn parameter_groups(&self) -> Vec<String> {
+        vec![String::from("linear combination"); self.coefficients.len()]
+    }
+}
+
+impl<T: Validate> Validate for LinearCombination<T> {
+    fn validate(&self) -> Result<()> {
+        check_invariant(
+            !self.components.is_empty() && self.coefficients.len() == self.components.len(),
+            COEFFICIENTS,
+        )?;
+        for component in &self.components {
+            component.validate()?;
+        }
+        check_invariant(
+            self.components
+                .iter()
+                .all(|c| c.num_particles() == self.components[0].num_particles()),
+            "all components must describe the same number of particles",
+        )
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        self.components[0].num_particles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Gaussian;
+    use ndarray::array;
+
+    #[test]
+    fn linear_in_components() {
+        let phi_a = Gaussian::new(0.8, array![[0.5, 0.0, 0.0]]);
+        let phi_b = Gaussian::new(0.8, array![[-0.5, 0.0, 0.0]]);
+        let bonding =
+            LinearCombination::new(array![1.0, 0.5], vec![phi_a.clone(), phi_b.clone()]).unwrap();
+        let cfg = array![[0.1, 0.3, -0.2]];
+
+        let value = phi_a.value(&cfg).unwrap() + 0.5 * phi_b.value(&cfg).unwrap();
+        let grad = phi_a.gradient(&cfg).unwrap() + 0.5 * phi_b.gradient(&cfg).unwrap();
+        let lapl = phi_a.laplacian(&cfg).unwrap() + 0.5 * phi_b.laplacian(&cfg).unwrap();
+        assert!((bonding.value(&cfg).unwrap() - value).abs() < 1e-12);
+        assert!(bonding.gradient(&cfg).unwrap().all_close(&grad, 1e-12));
+        assert!((bonding.laplacian(&cfg).unwrap() - lapl).abs() < 1e-12);
+
+        let par_grad = bonding.parameter_gradient(&cfg).unwrap();
+        assert!((par_grad[0] - phi_a.value(&cfg).unwrap()).abs() < 1e-12);
+        assert!((par_grad[1] - phi_b.value(&cfg).unwrap()).abs() < 1e-12);
+        assert_eq!(bonding.num_electrons(), 1);
+    }
+
+    #[test]
+    fn one_coefficient_per_component() {
+        let phi = Gaussian::new(0.8, array![[0.5, 0.0, 0.0]]);
+        assert!(LinearCombination::new(array![1.0, 0.5], vec![phi]).is_err());
+        assert!(LinearCombination::<Gaussian>::new(array![], vec![]).is_err());
+    }
+}
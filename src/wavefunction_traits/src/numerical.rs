@@ -0,0 +1,223 @@
+// Third party imports
+use ndarray::{Array1, Array2, Ix2};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::{check_invariant, Differentiate, Function, Optimize, Validate, Vgl, WaveFunction};
+use errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Finite difference stencil used by `NumericalDerivatives`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Stencil {
+    /// Three-point central differences, with $O(h^2)$ error.
+    Central,
+    /// Five-point central differences, with $O(h^4)$ error.
+    FourthOrder,
+}
+
+impl Stencil {
+    /// First and second derivative of $f$ at zero, given $f(0)$ and step $h$.
+    fn derivatives<F>(self, f: F, f0: f64, h: f64) -> Result<(f64, f64)>
+    where
+        F: Fn(f64) -> Result<f64>,
+    {
+        let (fp, fm) = (f(h)?, f(-h)?);
+        match self {
+            Stencil::Central => Ok(((fp - fm) / (2.0 * h), (fp - 2.0 * f0 + fm) / h.powi(2))),
+            Stencil::FourthOrder => {
+                let (fpp, fmm) = (f(2.0 * h)?, f(-2.0 * h)?);
+                Ok((
+                    (-fpp + 8.0 * fp - 8.0 * fm + fmm) / (12.0 * h),
+                    (-fpp + 16.0 * fp - 30.0 * f0 + 16.0 * fm - fmm) / (12.0 * h.powi(2)),
+                ))
+            }
+        }
+    }
+}
+
+/// Adapter that provides `Differentiate` for any wave function by finite
+/// differences, and replaces its `Optimize::parameter_gradient` by a finite
+/// difference in each parameter. Useful for prototyping wave functions before
+/// their analytic derivatives are written.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NumericalDerivatives<T> {
+    wave_function: T,
+    step: f64,
+    stencil: Stencil,
+}
+
+impl<T> NumericalDerivatives<T> {
+    pub fn new(wave_function: T, step: f64, stencil: Stencil) -> Self {
+        Self {
+            wave_function,
+            step,
+            stencil,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.wave_function
+    }
+}
+
+impl<T> NumericalDerivatives<T>
+where
+    T: Function<f64, D = Ix2>,
+{
+    /// First and second derivatives with respect to every coordinate.
+    fn coordinate_derivatives(&self, cfg: &Array2<f64>) -> Result<Array2<(f64, f64)>> {
+        let value = self.wave_function.value(cfg)?;
+        let mut derivs = Array2::from_elem(cfg.dim(), (0.0, 0.0));
+        for (idx, d) in derivs.indexed_iter_mut() {
+            let shifted = |h| {
+                let mut cfg = cfg.clone();
+                cfg[idx] += h;
+                self.wave_function.value(&cfg)
+            };
+            *d = self.stencil.derivatives(shifted, value, self.step)?;
+        }
+        Ok(derivs)
+    }
+}
+
+impl<T> Function<f64> for NumericalDerivatives<T>
+where
+    T: Function<f64, D = Ix2>,
+{
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        self.wave_function.value(cfg)
+    }
+}
+
+impl<T> Differentiate for NumericalDerivatives<T>
+where
+    T: Function<f64, D = Ix2>,
+{
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(self.coordinate_derivatives(cfg)?.mapv(|(first, _)| first))
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(self
+            .coordinate_derivatives(cfg)?
+            .iter()
+            .map(|(_, second)| second)
+            .sum())
+    }
+
+    fn vgl(&self, cfg: &Array2<f64>) -> Result<Vgl> {
+        let derivs = self.coordinate_derivatives(cfg)?;
+        Ok((
+            self.wave_function.value(cfg)?,
+            derivs.mapv(|(first, _)| first),
+            derivs.iter().map(|(_, second)| second).sum(),
+        ))
+    }
+}
+
+impl<T: WaveFunction> WaveFunction for NumericalDerivatives<T> {
+    fn num_electrons(&self) -> usize {
+        self.wave_function.num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.wave_function.num_up()
+    }
+
+    fn dimension(&self) -> usize {
+        self.wave_function.dimension()
+    }
+}
+
+impl<T> Optimize for NumericalDerivatives<T>
+where
+    T: Function<f64, D = Ix2> + Optimize + Clone,
+{
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let value = self.wave_function.value(cfg)?;
+        let nparm = self.wave_function.num_parameters();
+        let mut grad = Array1::<f64>::zeros(nparm);
+        for (p, g) in grad.iter_mut().enumerate() {
+            let shifted = |h| {
+                let mut deltap = Array1::zeros(nparm);
+                deltap[p] = h;
+                let mut wf = self.wave_function.clone();
+                wf.update_parameters(&deltap);
+                wf.value(cfg)
+            };
+            *g = self.stencil.derivatives(shifted, value, self.step)?.0;
+        }
+        Ok(grad)
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.wave_function.update_parameters(deltap);
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.wave_function.parameters()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.wave_function.num_parameters()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        self.wave_function.parameter_names()
+    }
+
+    fn parameter_groups(&self) -> Vec<String> {
+        self.wave_function.parameter_groups()
+    }
+
+    fn parameter_mask(&self) -> Array1<bool> {
+        self.wave_function.parameter_mask()
+    }
+}
+
+impl<T: Validate> Validate for NumericalDerivatives<T> {
+    fn validate(&self) -> Result<()> {
+        check_invariant(
+            self.step > 0.0,
+            "the finite difference step must be positive",
+        )?;
+        self.wave_function.validate()
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        self.wave_function.num_particles()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Gaussian;
+    use ndarray::array;
+
+    #[test]
+    fn matches_analytic_derivatives() {
+        let exact = Gaussian::new(0.9, array![[0.1, -0.2, 0.3], [0.0, 0.4, -0.1]]);
+        let cfg = array![[0.3, 0.1, -0.2], [-0.5, 0.2, 0.4]];
+        let grad = exact.gradient(&cfg).unwrap();
+        let lapl = exact.laplacian(&cfg).unwrap();
+        let par_grad = exact.parameter_gradient(&cfg).unwrap();
+        for &(stencil, step, tol) in &[
+            (Stencil::Central, 1e-4, 1e-6),
+            (Stencil::FourthOrder, 1e-2, 1e-7),
+        ] {
+            let numerical = NumericalDerivatives::new(exact.clone(), step, stencil);
+            assert!(numerical.gradient(&cfg).unwrap().all_close(&grad, tol));
+            assert!((numerical.laplacian(&cfg).unwrap() - lapl).abs() < 100.0 * tol);
+            assert!(numerical
+                .parameter_gradient(&cfg)
+                .unwrap()
+                .all_close(&par_grad, tol));
+        }
+    }
+}
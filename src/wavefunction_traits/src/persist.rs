@@ -0,0 +1,72 @@
+// Standard imports
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+// Third party imports
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+// First party imports
+use errors::Error::{self, InputError, SerializationError};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Invariants that deserialization alone does not enforce, e.g. the number of
+/// parameters of a Jastrow factor, or that both factors of a product describe
+/// the same number of particles. `Persist::load` rejects files that violate them.
+pub trait Validate {
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The number of particles, for wave functions defined for a fixed number.
+    fn num_particles(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Fail with an `InputError` describing the requirement if it does not hold,
+/// for arguments that would otherwise construct an invalid wave function.
+pub fn check_input(holds: bool, requirement: &str) -> Result<()> {
+    if holds {
+        Ok(())
+    } else {
+        Err(InputError(format!("Invalid input: {}", requirement)))
+    }
+}
+
+/// Fail with a `SerializationError` describing the invariant if it does not hold.
+pub fn check_invariant(holds: bool, invariant: &str) -> Result<()> {
+    if holds {
+        Ok(())
+    } else {
+        Err(SerializationError(format!(
+            "Invalid wave function: {}",
+            invariant
+        )))
+    }
+}
+
+/// Saving and loading of wave functions, including their variational parameters,
+/// as human-readable JSON. This allows e.g. a wave function optimized by
+/// `VmcRunner::run_optimization` to be used in a later DMC run. Implemented for
+/// every type that can be serialized with serde and implements `Validate`.
+pub trait Persist: Sized {
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<()>;
+
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self>;
+}
+
+impl<T: Serialize + DeserializeOwned + Validate> Persist for T {
+    fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(|e| SerializationError(e.to_string()))
+    }
+
+    fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let loaded: Self =
+            serde_json::from_reader(reader).map_err(|e| SerializationError(e.to_string()))?;
+        loaded.validate()?;
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,296 @@
+// Third party imports
+use ndarray::{s, stack, Array, Array1, Array2, Axis, Dimension, Ix2};
+use serde::{Deserialize, Serialize};
+// First party imports
+use crate::{
+    check_invariant, Differentiate, Field, Function, LogVgl, Optimize, Validate, Vgl, WaveFunction,
+};
+use errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Product $\psi = \psi_A \psi_B$ of two wave functions of the same configuration,
+/// e.g. a Slater determinant and a Jastrow factor.
+/// The variational parameters of the product are those of $\psi_A$ followed
+/// by those of $\psi_B$.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Product<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Product<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    pub fn first(&self) -> &A {
+        &self.a
+    }
+
+    pub fn second(&self) -> &B {
+        &self.b
+    }
+}
+
+impl<A, B, D, T> Function<T> for Product<A, B>
+where
+    A: Function<T, D = D>,
+    B: Function<T, D = D>,
+    D: Dimension,
+    T: Field,
+{
+    type D = D;
+
+    fn value(&self, cfg: &Array<f64, D>) -> Result<T> {
+        Ok(self.a.value(cfg)? * self.b.value(cfg)?)
+    }
+
+    fn log_value(&self, cfg: &Array<f64, D>) -> Result<(f64, T)> {
+        let (log_a, phase_a) = self.a.log_value(cfg)?;
+        let (log_b, phase_b) = self.b.log_value(cfg)?;
+        Ok((log_a + log_b, phase_a * phase_b))
+    }
+}
+
+impl<A, B, D, T> Differentiate<T> for Product<A, B>
+where
+    A: Function<T, D = D> + Differentiate<T, D = D>,
+    B: Function<T, D = D> + Differentiate<T, D = D>,
+    D: Dimension,
+    T: Field,
+{
+    type D = D;
+
+    fn gradient(&self, cfg: &Array<f64, D>) -> Result<Array<T, D>> {
+        let grad_a = self.a.gradient(cfg)?;
+        let grad_b = self.b.gradient(cfg)?;
+        Ok(grad_a * self.b.value(cfg)? + grad_b * self.a.value(cfg)?)
+    }
+
+    fn laplacian(&self, cfg: &Array<f64, D>) -> Result<T> {
+        // $\nabla^2(\psi_A\psi_B) = \psi_B\nabla^2\psi_A + \psi_A\nabla^2\psi_B
+        // + 2\nabla\psi_A\cdot\nabla\psi_B$
+        let cross = (&self.a.gradient(cfg)? * &self.b.gradient(cfg)?).sum();
+        Ok(self.b.value(cfg)? * self.a.laplacian(cfg)?
+            + self.a.value(cfg)? * self.b.laplacian(cfg)?
+            + cross.mul_real(2.0))
+    }
+
+    fn vgl(&self, cfg: &Array<f64, D>) -> Result<Vgl<D, T>> {
+        let (value_a, grad_a, lapl_a) = self.a.vgl(cfg)?;
+        let (value_b, grad_b, lapl_b) = self.b.vgl(cfg)?;
+        let cross = (&grad_a * &grad_b).sum();
+        Ok((
+            value_a * value_b,
+            grad_a * value_b + grad_b * value_a,
+            lapl_a * value_b + lapl_b * value_a + cross.mul_real(2.0),
+        ))
+    }
+
+    /// In the log domain the contributions of both factors simply add.
+    fn log_vgl(&self, cfg: &Array<f64, D>) -> Result<LogVgl<D, T>> {
+        let (log_a, phase_a, grad_a, lapl_a) = self.a.log_vgl(cfg)?;
+        let (log_b, phase_b, grad_b, lapl_b) = self.b.log_vgl(cfg)?;
+        Ok((
+            log_a + log_b,
+            phase_a * phase_b,
+            grad_a + grad_b,
+            lapl_a + lapl_b,
+        ))
+    }
+}
+
+/// The electron count and any cached state of the product are those of the
+/// first factor, e.g. a Slater determinant. The second factor, e.g. a Jastrow
+/// factor, only needs to be differentiable, and its contribution to ratios
+/// is evaluated directly.
+impl<A, B, T> WaveFunction<T> for Product<A, B>
+where
+    A: WaveFunction<T> + Function<T, D = Ix2> + Differentiate<T, D = Ix2>,
+    B: Function<T, D = Ix2> + Differentiate<T, D = Ix2>,
+    T: Field,
+{
+    fn num_electrons(&self) -> usize {
+        self.a.num_electrons()
+    }
+
+    fn num_up(&self) -> usize {
+        self.a.num_up()
+    }
+
+    fn dimension(&self) -> usize {
+        self.a.dimension()
+    }
+
+    fn ratio(&self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<T> {
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(idx).assign(pos);
+        let (log_new, phase_new) = self.b.log_value(&cfg_new)?;
+        let (log_old, phase_old) = self.b.log_value(cfg)?;
+        let ratio_b = (phase_new / phase_old).mul_real((log_new - log_old).exp());
+        Ok(self.a.ratio(cfg, pos, idx)? * ratio_b)
+    }
+
+    /// $\nabla_i(\psi_A\psi_B)/(\psi_A\psi_B) = \nabla_i\psi_A/\psi_A + \nabla_i\psi_B/\psi_B$
+    fn gradient_ratio(
+        &self,
+        cfg: &Array2<f64>,
+        pos: &Array1<f64>,
+        idx: usize,
+    ) -> Result<Array1<T>> {
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(idx).assign(pos);
+        let grad_b = self.b.log_vgl(&cfg_new)?.2.row(idx).to_owned();
+        Ok(self.a.gradient_ratio(cfg, pos, idx)? + grad_b)
+    }
+
+    fn refresh(&mut self, cfg: &Array2<f64>) -> Result<()> {
+        self.a.refresh(cfg)
+    }
+
+    fn accept_move(&mut self, cfg: &Array2<f64>, pos: &Array1<f64>, idx: usize) -> Result<()> {
+        self.a.accept_move(cfg, pos, idx)
+    }
+
+    fn reject_move(&mut self, idx: usize) {
+        self.a.reject_move(idx)
+    }
+}
+
+impl<A, B> Optimize for Product<A, B>
+where
+    A: Function<f64, D = Ix2> + Optimize,
+    B: Function<f64, D = Ix2> + Optimize,
+{
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let grad_a = self.a.parameter_gradient(cfg)? * self.b.value(cfg)?;
+        let grad_b = self.b.parameter_gradient(cfg)? * self.a.value(cfg)?;
+        Ok(stack![Axis(0), grad_a, grad_b])
+    }
+
+    fn log_parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let grad_a = self.a.log_parameter_gradient(cfg)?;
+        let grad_b = self.b.log_parameter_gradient(cfg)?;
+        Ok(stack![Axis(0), grad_a, grad_b])
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        let na = self.a.num_parameters();
+        self.a.update_parameters(&deltap.slice(s![..na]).to_owned());
+        self.b.update_parameters(&deltap.slice(s![na..]).to_owned());
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        stack![Axis(0), self.a.parameters(), self.b.parameters()]
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.a.num_parameters() + self.b.num_parameters()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        let mut names = self.a.parameter_names();
+        names.extend(self.b.parameter_names());
+        names
+    }
+
+    fn parameter_groups(&self) -> Vec<String> {
+        let mut groups = self.a.parameter_groups();
+        groups.extend(self.b.parameter_groups());
+        groups
+    }
+
+    fn parameter_mask(&self) -> Array1<bool> {
+        stack![Axis(0), self.a.parameter_mask(), self.b.parameter_mask()]
+    }
+}
+
+impl<A: Validate, B: Validate> Validate for Product<A, B> {
+    fn validate(&self) -> Result<()> {
+        self.a.validate()?;
+        self.b.validate()?;
+        match (self.a.num_particles(), self.b.num_particles()) {
+            (Some(na), Some(nb)) => check_invariant(
+                na == nb,
+                "both factors must describe the same number of particles",
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn num_particles(&self) -> Option<usize> {
+        self.a.num_particles().or_else(|| self.b.num_particles())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Gaussian;
+    use ndarray::array;
+
+    const H: f64 = 1e-4;
+
+    fn product() -> Product<Gaussian, Gaussian> {
+        Product::new(
+            Gaussian::new(0.7, array![[0.1, 0.0, -0.2], [0.0, 0.3, 0.0]]),
+            Gaussian::new(1.3, array![[-0.4, 0.2, 0.1], [0.5, 0.0, 0.2]]),
+        )
+    }
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let wf = product();
+        let cfg = array![[0.2, -0.1, 0.3], [-0.3, 0.4, 0.1]];
+        let grad = wf.gradient(&cfg).unwrap();
+        let value = wf.value(&cfg).unwrap();
+        let mut lapl = 0.0;
+        for (idx, g) in grad.indexed_iter() {
+            let mut step = Array2::zeros(cfg.dim());
+            step[idx] = H;
+            let forward = wf.value(&(&cfg + &step)).unwrap();
+            let backward = wf.value(&(&cfg - &step)).unwrap();
+            assert!((g - (forward - backward) / (2.0 * H)).abs() < 1e-6);
+            lapl += (forward - 2.0 * value + backward) / H.powi(2);
+        }
+        assert!((wf.laplacian(&cfg).unwrap() - lapl).abs() < 1e-4);
+    }
+
+    #[test]
+    fn parameters_are_concatenated() {
+        let mut wf = product();
+        let cfg = array![[0.2, -0.1, 0.3], [-0.3, 0.4, 0.1]];
+        assert_eq!(wf.parameters(), array![0.7, 1.3]);
+        let grad = wf.parameter_gradient(&cfg).unwrap();
+        for p in 0..wf.num_parameters() {
+            let mut step = Array1::zeros(wf.num_parameters());
+            step[p] = H;
+            let mut forward = wf.clone();
+            forward.update_parameters(&step);
+            let mut backward = wf.clone();
+            backward.update_parameters(&(-step));
+            let fd = (forward.value(&cfg).unwrap() - backward.value(&cfg).unwrap()) / (2.0 * H);
+            assert!((grad[p] - fd).abs() < 1e-6);
+        }
+        wf.update_parameters(&array![0.1, -0.2]);
+        assert!((wf.first().parameters()[0] - 0.8).abs() < 1e-12);
+        assert!((wf.second().parameters()[0] - 1.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ratios_match_values() {
+        let wf = product();
+        let cfg = array![[0.2, -0.1, 0.3], [-0.3, 0.4, 0.1]];
+        let pos = array![0.5, 0.1, -0.2];
+        let mut cfg_new = cfg.clone();
+        cfg_new.row_mut(1).assign(&pos);
+        let ratio = wf.value(&cfg_new).unwrap() / wf.value(&cfg).unwrap();
+        assert!((wf.ratio(&cfg, &pos, 1).unwrap() - ratio).abs() < 1e-12);
+        let grad = wf.gradient(&cfg_new).unwrap().row(1).to_owned() / wf.value(&cfg_new).unwrap();
+        assert!(wf
+            .gradient_ratio(&cfg, &pos, 1)
+            .unwrap()
+            .all_close(&grad, 1e-12));
+    }
+}
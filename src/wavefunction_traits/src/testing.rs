@@ -0,0 +1,73 @@
+// Test helpers shared between the wave function combinators.
+use ndarray::{array, Array1, Array2, Axis, Ix2};
+use ndarray_linalg::Norm;
+
+use crate::{Differentiate, Function, Optimize, WaveFunction};
+use errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Gaussian $\exp(-\alpha|x - x_0|^2)$ with variational parameter $\alpha$.
+#[derive(Clone)]
+pub struct Gaussian {
+    params: Array1<f64>,
+    center: Array2<f64>,
+}
+
+impl Gaussian {
+    pub fn new(alpha: f64, center: Array2<f64>) -> Self {
+        Self {
+            params: array![alpha],
+            center,
+        }
+    }
+}
+
+impl Function<f64> for Gaussian {
+    type D = Ix2;
+
+    fn value(&self, cfg: &Array2<f64>) -> Result<f64> {
+        Ok(f64::exp(
+            -self.params[0] * (cfg - &self.center).norm_l2().powi(2),
+        ))
+    }
+}
+
+impl Differentiate for Gaussian {
+    type D = Ix2;
+
+    fn gradient(&self, cfg: &Array2<f64>) -> Result<Array2<f64>> {
+        Ok(-2.0 * self.params[0] * self.value(cfg)? * (cfg - &self.center))
+    }
+
+    fn laplacian(&self, cfg: &Array2<f64>) -> Result<f64> {
+        let r2 = (cfg - &self.center).norm_l2().powi(2);
+        let alpha = self.params[0];
+        Ok(self.value(cfg)? * (4.0 * alpha.powi(2) * r2 - 2.0 * alpha * cfg.len() as f64))
+    }
+}
+
+impl Optimize for Gaussian {
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let r2 = (cfg - &self.center).norm_l2().powi(2);
+        Ok(array![-r2 * self.value(cfg)?])
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.params += deltap;
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
+    }
+
+    fn num_parameters(&self) -> usize {
+        1
+    }
+}
+
+impl WaveFunction for Gaussian {
+    fn num_electrons(&self) -> usize {
+        self.center.len_of(Axis(0))
+    }
+}
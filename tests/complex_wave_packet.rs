@@ -0,0 +1,95 @@
+// This tests sampling of a complex wave function: a Gaussian wave packet
+// moving with momentum $\mathbf{k}$, whose kinetic energy is known exactly
+use mole::prelude::*;
+use ndarray::{array, Array1, Array2, Ix2};
+use ndarray_linalg::c64;
+
+use rand::{SeedableRng, StdRng};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+struct EmptyLogger;
+impl Log for EmptyLogger {
+    fn log(&mut self, _data: &HashMap<String, Vec<OperatorValue>>) -> String {
+        String::new()
+    }
+}
+
+// $\psi = \exp(i\mathbf{k}\cdot\mathbf{r} - \alpha r^2)$
+#[derive(Clone)]
+struct WavePacket {
+    k: Array1<f64>,
+    alpha: f64,
+}
+
+impl WavePacket {
+    // $\nabla \ln\psi = i\mathbf{k} - 2\alpha\mathbf{r}$
+    fn log_gradient(&self, x: &Array2<f64>) -> Array1<c64> {
+        let r = x.row(0);
+        self.k
+            .iter()
+            .zip(r.iter())
+            .map(|(k, r)| c64::new(-2.0 * self.alpha * r, *k))
+            .collect()
+    }
+}
+
+impl Function<c64> for WavePacket {
+    type D = Ix2;
+
+    fn value(&self, x: &Array2<f64>) -> Result<c64> {
+        let r = x.row(0);
+        Ok(c64::new(-self.alpha * r.dot(&r), self.k.dot(&r)).exp())
+    }
+}
+
+impl Differentiate<c64> for WavePacket {
+    type D = Ix2;
+
+    fn gradient(&self, x: &Array2<f64>) -> Result<Array2<c64>> {
+        let value = self.value(x)?;
+        Ok(self
+            .log_gradient(x)
+            .mapv(|g| g * value)
+            .into_shape((1, x.cols()))?)
+    }
+
+    fn laplacian(&self, x: &Array2<f64>) -> Result<c64> {
+        let grad = self.log_gradient(x);
+        let dim = x.cols() as f64;
+        Ok(self.value(x)? * (grad.dot(&grad) - 2.0 * self.alpha * dim))
+    }
+}
+
+impl WaveFunction<c64> for WavePacket {
+    fn num_electrons(&self) -> usize {
+        1
+    }
+}
+
+#[test]
+fn wave_packet_kinetic_energy() {
+    let wf = WavePacket {
+        k: array![0.5, -1.0, 0.25],
+        alpha: 0.5,
+    };
+    let mut obs: HashMap<String, Box<dyn LocalOperator<WavePacket, c64>>> = HashMap::new();
+    obs.insert("Kinetic energy".to_string(), Box::new(KineticEnergy::new()));
+
+    let metrop = MetropolisDiffuse::from_rng(0.5, StdRng::from_seed([0_u8; 32]));
+    let sampler = Sampler::new(wf.clone(), metrop, &obs).unwrap();
+    let result = Runner::new(sampler, EmptyLogger).run(20000, 100).unwrap();
+
+    let samples: Vec<c64> = result.data["Kinetic energy"]
+        .iter()
+        .map(|x| *x.get_complex().unwrap())
+        .collect();
+    let mean = samples.iter().sum::<c64>() / samples.len() as f64;
+
+    // $|\psi|^2$ is a Gaussian with $\langle r^2 \rangle = 3/(4\alpha)$, so that
+    // $\langle T \rangle = k^2/2 + 3\alpha/2$, while $\langle \mathbf{r} \rangle = 0$
+    // makes the imaginary part vanish
+    let exact = 0.5 * wf.k.dot(&wf.k) + 1.5 * wf.alpha;
+    assert!((mean.re - exact).abs() < 0.05);
+    assert!(mean.im.abs() < 0.05);
+}
@@ -0,0 +1,153 @@
+// This tests the homogeneous electron gas in a periodic cell: sampling the
+// plane-wave Slater determinant reproduces its Hartree-Fock energy, VMC
+// optimizes the RPA Jastrow factor, and DMC with the Slater-Jastrow wave
+// function reproduces the Ceperley-Alder energies after a finite size correction
+use mole::prelude::*;
+use ndarray::{Array1, Array2, Axis};
+use ndarray_rand::RandomExt;
+use rand::distributions::Range;
+use rand::{SeedableRng, StdRng};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+struct EmptyLogger;
+impl Log for EmptyLogger {
+    fn log(&mut self, _data: &HashMap<String, Vec<OperatorValue>>) -> String {
+        String::new()
+    }
+}
+
+// electrons distributed uniformly over the cell
+fn initial_configuration(heg: &HomogeneousElectronGas, rng: &mut StdRng) -> Array2<f64> {
+    let num_electrons = heg.num_up() + heg.num_down();
+    Array2::random_using((num_electrons, 3), Range::new(0.0, 1.0), rng).dot(heg.cell().lattice())
+}
+
+// mean and standard error of the mean of correlated samples, from block averages
+fn blocked_mean(samples: &[OperatorValue], block_size: usize) -> (f64, f64) {
+    let samples: Vec<f64> = samples.iter().map(|x| *x.get_scalar().unwrap()).collect();
+    let blocks = Array1::from_vec(
+        samples
+            .chunks(block_size)
+            .map(|block| block.iter().sum::<f64>() / block.len() as f64)
+            .collect(),
+    );
+    let mean = *blocks.mean_axis(Axis(0)).first().unwrap();
+    let error = *blocks.std_axis(Axis(0), 1.0).first().unwrap() / (blocks.len() as f64).sqrt();
+    (mean, error)
+}
+
+#[test]
+fn slater_determinant_gives_hartree_fock_energy() {
+    let heg = HomogeneousElectronGas::new(7, 7, 2.0).unwrap();
+    let mut rng = StdRng::from_seed([0_u8; 32]);
+    let cfg = initial_configuration(&heg, &mut rng);
+    let metrop = MetropolisDiffuse::from_rng(1.0, rng).with_cell(heg.cell().clone());
+
+    let obs = operators! {
+        "Energy" => heg.hamiltonian()
+    };
+    let sampler =
+        Sampler::with_initial_configuration(heg.slater_determinant().unwrap(), metrop, &obs, cfg)
+            .unwrap();
+    let result = Runner::new(sampler, EmptyLogger).run(1000, 50).unwrap();
+
+    let (energy, error) = blocked_mean(&result.data["Energy"], 50);
+    let exact = heg.hartree_fock_energy().unwrap();
+    assert!((energy - exact).abs() < 3.0 * error);
+}
+
+#[test]
+fn vmc_optimizes_rpa_jastrow() {
+    // start from half the RPA value of $A$, which lowers the energy when increased
+    let heg = HomogeneousElectronGas::new(7, 7, 5.0).unwrap();
+    let rpa = heg.rpa_jastrow().parameters()[0];
+    let wf = Product::new(
+        heg.slater_determinant().unwrap(),
+        RpaJastrow::new(heg.cell().clone(), 7, 0.5 * rpa),
+    );
+    let mut rng = StdRng::from_seed([0_u8; 32]);
+    let cfg = initial_configuration(&heg, &mut rng);
+    let metrop = MetropolisDiffuse::from_rng(1.0, rng).with_cell(heg.cell().clone());
+
+    let obs = operators! {
+        "Energy" => heg.hamiltonian(),
+        "Parameter gradient" => ParameterGradient
+    };
+    let sampler = Sampler::with_initial_configuration(wf, metrop, &obs, cfg).unwrap();
+    let vmc = VmcRunner::new(sampler, StochasticReconfiguration::new(0.1), EmptyLogger);
+    let (wf, energies, errors) = vmc.run_optimization(3, 400, 20, 4).unwrap();
+
+    assert!(wf.parameters()[0] > 0.5 * rpa);
+    let (energy, error) = (energies[2], errors[2]);
+    assert!(energy + 3.0 * error < heg.hartree_fock_energy().unwrap());
+}
+
+// DMC energy and its error per electron, corrected for the finite size of the
+// cell as by Chiesa et al., Phys. Rev. Lett. 97, 076404 (2006): the kinetic
+// energy of the filled shells is replaced by its limit $0.3 k_F^2$, and the
+// potential energy is raised by $\omega_p / 4N$ with the plasma frequency
+// $\omega_p = \sqrt{3/r_s^3}$
+fn corrected_dmc_energy(
+    heg: &HomogeneousElectronGas,
+    num_walkers: usize,
+    num_steps: usize,
+) -> (f64, f64) {
+    let rs = heg.density_parameter();
+    let num_electrons = (heg.num_up() + heg.num_down()) as f64;
+    let time_step = 0.05 * rs.powi(2);
+    let metrop = MetropolisDiffuse::from_rng(time_step, StdRng::from_seed([1_u8; 32]))
+        .with_cell(heg.cell().clone());
+    let mut dmc = DmcRunner::new(
+        heg.wave_function().unwrap(),
+        num_walkers,
+        heg.hartree_fock_energy().unwrap(),
+        heg.hamiltonian(),
+        metrop,
+        SRBrancher::new(),
+    );
+    let block_size = num_steps / 20;
+    let (energies, errors) = dmc.diffuse(time_step, num_steps, block_size, 5);
+
+    let fermi_wave_vector = (9.0 * std::f64::consts::PI / 4.0).cbrt() / rs;
+    let shell_kinetic = 2.0
+        * heg
+            .wave_vectors(heg.num_up())
+            .unwrap()
+            .iter()
+            .map(|k| 0.5 * k.dot(k))
+            .sum::<f64>()
+        / num_electrons;
+    let plasma_frequency = (3.0 / rs.powi(3)).sqrt();
+    let correction =
+        0.3 * fermi_wave_vector.powi(2) - shell_kinetic + plasma_frequency / (4.0 * num_electrons);
+    (
+        energies.last().unwrap() / num_electrons + correction,
+        errors.last().unwrap() / num_electrons,
+    )
+}
+
+// Finite size error per electron left after the correction with 14
+// electrons. Long runs (100 walkers, 2000 steps) give corrected energies
+// 1.0 and 1.6 milli-Hartree below Ceperley-Alder at $r_s = 1$ and 5.
+const FINITE_SIZE_ERROR: f64 = 2e-3;
+
+#[test]
+fn dmc_reproduces_ceperley_alder_energy() {
+    let rs = 5.0;
+    let heg = HomogeneousElectronGas::new(7, 7, rs).unwrap();
+    let (energy, error) = corrected_dmc_energy(&heg, 10, 200);
+    let tolerance = 3.0 * error + FINITE_SIZE_ERROR;
+    assert!((energy - ceperley_alder_energy(rs)).abs() < tolerance);
+}
+
+#[test]
+#[ignore]
+fn dmc_reproduces_ceperley_alder_energies() {
+    for &rs in &[1.0, 5.0] {
+        let heg = HomogeneousElectronGas::new(7, 7, rs).unwrap();
+        let (energy, error) = corrected_dmc_energy(&heg, 100, 2000);
+        let tolerance = 3.0 * error + FINITE_SIZE_ERROR;
+        assert!((energy - ceperley_alder_energy(rs)).abs() < tolerance);
+    }
+}
@@ -1,7 +1,6 @@
 use metropolis::MetropolisBox;
 use mole::prelude::*;
-use ndarray::{array, Array1, Array2, Axis, Ix2};
-use ndarray_linalg::Norm;
+use ndarray::{array, Array1, Axis};
 use operator::{
     ElectronicHamiltonian, ElectronicPotential, IonicPotential, KineticEnergy, OperatorValue,
 };
@@ -17,86 +16,6 @@ impl Log for MockLogger {
     }
 }
 
-#[derive(Clone)]
-struct STO {
-    alpha: f64,
-}
-
-impl STO {
-    pub fn new(alpha: f64) -> Self {
-        Self { alpha }
-    }
-
-    pub fn value(&self, x: &Array2<f64>) -> f64 {
-        f64::exp(-self.alpha * x.norm_l2())
-    }
-
-    pub fn gradient(&self, x: &Array2<f64>) -> Array2<f64> {
-        -self.alpha * self.value(x) / x.norm_l2() * x
-    }
-
-    pub fn laplacian(&self, x: &Array2<f64>) -> f64 {
-        self.alpha * self.value(x) / x.norm_l2() * (self.alpha * x.norm_l2() - 2.0)
-    }
-
-    pub fn parameter_gradient(&self, x: &Array2<f64>) -> f64 {
-        -x.norm_l2() * self.value(x)
-    }
-
-    pub fn update_parameters(&mut self, deltap: f64) {
-        self.alpha += deltap;
-    }
-}
-
-#[derive(Clone)]
-struct H2WF {
-    r: Array2<f64>,
-    phi: STO,
-}
-
-impl H2WF {
-    pub fn new(r: f64, alpha: f64) -> Self {
-        Self {
-            r: array![[r, 0.0, 0.0]],
-            phi: STO::new(alpha),
-        }
-    }
-}
-
-impl Function<f64> for H2WF {
-    type D = Ix2;
-
-    fn value(&self, x: &Array2<f64>) -> Result<f64> {
-        let r1 = &(x - &(0.5 * &self.r));
-        let r2 = &(x + &(0.5 * &self.r));
-        Ok(self.phi.value(&r1) * self.phi.value(&r2))
-    }
-}
-
-impl Differentiate for H2WF {
-    type D = Ix2;
-
-    fn gradient(&self, x: &Array2<f64>) -> Result<Array2<f64>> {
-        let r1 = &(x - &(0.5 * &self.r));
-        let r2 = &(x + &(0.5 * &self.r));
-        Ok(self.phi.value(r1) * self.phi.gradient(r2) + self.phi.value(r2) * self.phi.gradient(r1))
-    }
-
-    fn laplacian(&self, x: &Array2<f64>) -> Result<f64> {
-        let r1 = &(x - &(0.5 * &self.r));
-        let r2 = &(x + &(0.5 * &self.r));
-        Ok(self.phi.value(r1) * self.phi.laplacian(r2)
-            + self.phi.value(r2) * self.phi.laplacian(r1)
-            + 2.0 * (&self.phi.gradient(r1) * &self.phi.gradient(r2)).sum())
-    }
-}
-
-impl WaveFunction for H2WF {
-    fn num_electrons(&self) -> usize {
-        1
-    }
-}
-
 #[test]
 fn hydrogen_molecular_ion_lcao() {
     let ion_pos = array![[-1.25, 0.0, 0.0], [1.25, 0.0, 0.0]];
@@ -105,7 +24,16 @@ fn hydrogen_molecular_ion_lcao() {
     //let orbitals = vec![Orbital::new(array![[1.0], [1.0]], basis.clone())];
 
     //let wave_function = SingleDeterminant::new(orbitals).unwrap();
-    let wave_function = H2WF::new(2.5, 1.0);
+    let orbital = Product::new(
+        SlaterOrbital::new(
+            array![-1.25, 0.0, 0.0],
+            1,
+            1.0,
+            AngularPart::spherical(0, 0),
+        ),
+        SlaterOrbital::new(array![1.25, 0.0, 0.0], 1, 1.0, AngularPart::spherical(0, 0)),
+    );
+    let wave_function = SlaterDeterminant::new(vec![orbital]);
 
     let kinetic = KineticEnergy::new();
     let potential_ions = IonicPotential::new(ion_pos, array![1, 1]);
@@ -0,0 +1,155 @@
+// This tests saving wave functions to file and loading them again,
+// e.g. to hand an optimized wave function from a VMC run to a DMC run
+use mole::prelude::*;
+use ndarray::{array, Array1, Array2, Ix2};
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+fn orbital(center: Array1<f64>, alpha: f64) -> GaussianOrbital {
+    GaussianOrbital::new(
+        center,
+        array![alpha],
+        array![1.0],
+        AngularPart::spherical(0, 0),
+    )
+}
+
+fn slater_jastrow() -> Product<BackflowDeterminant<GaussianOrbital>, Jastrow> {
+    let up = vec![
+        orbital(array![0.0, 0.0, 0.7], 0.8),
+        orbital(array![0.0, 0.0, -0.7], 1.3),
+    ];
+    let down = vec![orbital(array![0.0, 0.0, 0.7], 0.8)];
+    let backflow = Backflow::new(array![0.1, -0.05], 0.5);
+    Product::new(
+        BackflowDeterminant::new(up, down, backflow),
+        Jastrow::new(array![0.5, 1.0, 0.1], 1.0).unwrap(),
+    )
+}
+
+// unique per test and process, so that parallel test runs do not collide
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("mole_persistence_{}_{}.json", name, process::id()))
+}
+
+fn assert_same<T>(wf: &T, loaded: &T, cfg: &Array2<f64>)
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2> + Optimize + WaveFunction,
+{
+    let (log_value, sign, grad, lapl) = wf.log_vgl(cfg).unwrap();
+    let (log_value_l, sign_l, grad_l, lapl_l) = loaded.log_vgl(cfg).unwrap();
+    assert_eq!(log_value, log_value_l);
+    assert_eq!(sign, sign_l);
+    assert_eq!(grad, grad_l);
+    assert_eq!(lapl, lapl_l);
+    assert_eq!(wf.parameters(), loaded.parameters());
+    assert_eq!(wf.num_electrons(), loaded.num_electrons());
+}
+
+#[test]
+fn optimized_parameters_survive_round_trip() {
+    let mut wf = slater_jastrow();
+    wf.update_parameters(&array![0.02, 0.01, -0.1, 0.05, 0.03]);
+    let path = temp_path("slater_jastrow");
+    wf.save(&path).unwrap();
+
+    let loaded = <Product<BackflowDeterminant<GaussianOrbital>, Jastrow>>::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    let cfg = array![[0.3, 0.1, 0.4], [-0.5, 0.6, -0.2], [0.1, -0.7, 0.5]];
+    assert_same(&wf, &loaded, &cfg);
+}
+
+#[test]
+fn cached_inverse_is_rebuilt_after_loading() {
+    let mut wf = SpinDeterminantProduct::new(
+        vec![orbital(array![0.0, 0.0, 0.7], 0.8)],
+        vec![orbital(array![0.0, 0.0, -0.7], 0.8)],
+    );
+    let cfg = array![[0.3, 0.1, 0.4], [-0.5, 0.6, -0.2]];
+    wf.refresh(&cfg).unwrap();
+    let path = temp_path("determinant");
+    wf.save(&path).unwrap();
+
+    let loaded = <SpinDeterminantProduct<GaussianOrbital>>::load(&path);
+    fs::remove_file(&path).unwrap();
+    let mut loaded = loaded.unwrap();
+    loaded.refresh(&cfg).unwrap();
+    let pos = array![0.2, -0.1, 0.3];
+    assert_eq!(
+        wf.ratio(&cfg, &pos, 1).unwrap(),
+        loaded.ratio(&cfg, &pos, 1).unwrap()
+    );
+}
+
+#[test]
+fn loading_missing_file_fails() {
+    let path = temp_path("does_not_exist");
+    match Jastrow::load(&path) {
+        Err(Error::IoError(_)) => (),
+        _ => panic!("Expected an IO error"),
+    }
+}
+
+#[test]
+fn loading_invalid_wave_functions_fails() {
+    // a hand-edited file with too few parameters for the Pade polynomial
+    let path = temp_path("invalid_jastrow");
+    let json = r#"{"parameters": {"v": 1, "dim": [1], "data": [0.5]}, "kappa": 1.0}"#;
+    fs::write(&path, json).unwrap();
+    let loaded = Jastrow::load(&path);
+    fs::remove_file(&path).unwrap();
+    match loaded {
+        Err(Error::SerializationError(_)) => (),
+        _ => panic!("Expected a serialization error"),
+    }
+
+    // two orbitals, but a Jastrow factor for three particles
+    let path = temp_path("invalid_product");
+    let wf = Product::new(
+        SpinDeterminantProduct::new(
+            vec![orbital(array![0.0, 0.0, 0.7], 0.8)],
+            vec![orbital(array![0.0, 0.0, -0.7], 0.8)],
+        ),
+        McMillanJastrow::new(3, 1.1),
+    );
+    wf.save(&path).unwrap();
+    let loaded = <Product<SpinDeterminantProduct<GaussianOrbital>, McMillanJastrow>>::load(&path);
+    fs::remove_file(&path).unwrap();
+    match loaded {
+        Err(Error::SerializationError(_)) => (),
+        _ => panic!("Expected a serialization error"),
+    }
+}
+
+#[test]
+fn cells_are_rebuilt_from_the_lattice() {
+    // only the lattice is stored, so editing it by hand changes the
+    // reciprocal lattice and the cut-off of the Jastrow factor consistently
+    let path = temp_path("edited_cell");
+    let json = r#"{
+        "cell": {"lattice": {"v": 1, "dim": [3, 3], "data": [3, 0, 0, 0, 3, 0, 0, 0, 3]}},
+        "num_up": 1,
+        "parameters": {"v": 1, "dim": [1], "data": [0.5]}
+    }"#;
+    fs::write(&path, json).unwrap();
+    let loaded = RpaJastrow::load(&path);
+    fs::remove_file(&path).unwrap();
+    let expected = RpaJastrow::new(SimulationCell::cubic(3.0).unwrap(), 1, 0.5);
+    let cfg = array![[0.1, 0.2, 0.3], [1.3, 0.4, -0.2]];
+    assert_eq!(
+        expected.log_vgl(&cfg).unwrap(),
+        loaded.unwrap().log_vgl(&cfg).unwrap()
+    );
+
+    // linearly dependent lattice vectors
+    let path = temp_path("singular_cell");
+    let json = r#"{"lattice": {"v": 1, "dim": [2, 2], "data": [1, 2, 2, 4]}}"#;
+    fs::write(&path, json).unwrap();
+    let loaded = SimulationCell::load(&path);
+    fs::remove_file(&path).unwrap();
+    match loaded {
+        Err(Error::SerializationError(_)) => (),
+        _ => panic!("Expected a serialization error"),
+    }
+}
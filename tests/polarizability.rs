@@ -0,0 +1,112 @@
+// This tests the finite-field polarizability of an electron in an anisotropic
+// harmonic trap, for which $\alpha = 1/\omega^2$ along each axis
+use mole::prelude::*;
+use ndarray::{array, Array1, Array2, Axis, Ix2};
+
+use rand::{SeedableRng, StdRng};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+struct EmptyLogger;
+impl Log for EmptyLogger {
+    fn log(&mut self, _data: &HashMap<String, Vec<OperatorValue>>) -> String {
+        String::new()
+    }
+}
+
+// $\psi = \exp(-\sum_d \omega_d (x_d - c_d)^2 / 2)$, the trap ground state
+// displaced to the center $\mathbf{c}$, which is exact in a uniform field
+#[derive(Clone)]
+struct DisplacedGaussian {
+    frequencies: Array1<f64>,
+    center: Array1<f64>,
+}
+
+impl DisplacedGaussian {
+    fn displacement(&self, x: &Array2<f64>) -> Array1<f64> {
+        x.row(0).to_owned() - &self.center
+    }
+}
+
+impl Function<f64> for DisplacedGaussian {
+    type D = Ix2;
+
+    fn value(&self, x: &Array2<f64>) -> Result<f64> {
+        let d = self.displacement(x);
+        Ok((-0.5 * (&self.frequencies * &d).dot(&d)).exp())
+    }
+}
+
+impl Differentiate for DisplacedGaussian {
+    type D = Ix2;
+
+    fn gradient(&self, x: &Array2<f64>) -> Result<Array2<f64>> {
+        let grad = -&self.frequencies * &self.displacement(x) * self.value(x)?;
+        Ok(grad.insert_axis(Axis(0)))
+    }
+
+    fn laplacian(&self, x: &Array2<f64>) -> Result<f64> {
+        let w_d = &self.frequencies * &self.displacement(x);
+        Ok(self.value(x)? * (w_d.dot(&w_d) - self.frequencies.sum()))
+    }
+}
+
+impl WaveFunction for DisplacedGaussian {
+    fn num_electrons(&self) -> usize {
+        1
+    }
+}
+
+impl Optimize for DisplacedGaussian {
+    fn parameter_gradient(&self, x: &Array2<f64>) -> Result<Array1<f64>> {
+        Ok(&self.frequencies * &self.displacement(x) * self.value(x)?)
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.center += deltap;
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.center.clone()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.center.len()
+    }
+}
+
+#[test]
+fn harmonic_trap_polarizability() {
+    let frequencies = array![1.0, 2.0, 1.5];
+    let hamiltonian =
+        OperatorSum::new(KineticEnergy::new(), HarmonicTrap::new(frequencies.clone()));
+    let polarizability = FiniteFieldPolarizability::new(hamiltonian, array![0.0, 0.1, 0.0]);
+
+    let zero = DisplacedGaussian {
+        frequencies,
+        center: Array1::zeros(3),
+    };
+
+    // optimize the wave functions in the fields $\pm\mathbf{E}$
+    let optimize = |sign: f64, seed: u8| {
+        let obs = operators! {
+            "Energy" => polarizability.hamiltonian(sign),
+            "Parameter gradient" => ParameterGradient
+        };
+        let metrop = MetropolisDiffuse::from_rng(0.5, StdRng::from_seed([seed; 32]));
+        let sampler = Sampler::new(zero.clone(), metrop, &obs).unwrap();
+        let vmc = VmcRunner::new(sampler, SteepestDescent::new(0.2), EmptyLogger);
+        vmc.run_optimization(15, 1000, 10, 4).unwrap().0
+    };
+    let plus = optimize(1.0, 1);
+    let minus = optimize(-1.0, 2);
+    // the exact displacement is $\mathbf{E}/\omega^2$
+    assert!((plus.center[1] - 0.025).abs() < 1e-3);
+    assert!((minus.center[1] + 0.025).abs() < 1e-3);
+
+    let metrop = MetropolisDiffuse::from_rng(0.5, StdRng::from_seed([3_u8; 32]));
+    let (alpha, error) = polarizability
+        .run(zero, plus, minus, metrop, 10000, 100)
+        .unwrap();
+    assert!((alpha - 0.25).abs() < (3.0 * error).max(1e-3));
+}
@@ -0,0 +1,126 @@
+// This tests VMC optimization of a single electron
+// in a two-dimensional parabolic quantum dot
+use mole::prelude::*;
+use ndarray::{array, Array1, Array2, Axis, Ix2};
+use ndarray_linalg::Norm;
+
+use rand::{SeedableRng, StdRng};
+use std::collections::HashMap;
+
+#[derive(Clone)]
+struct EmptyLogger;
+impl Log for EmptyLogger {
+    fn log(&mut self, _data: &HashMap<String, Vec<OperatorValue>>) -> String {
+        String::new()
+    }
+}
+
+// Parabolic confinement $V = \omega^2 r^2 / 2$ in the plane
+struct QuantumDotHamiltonian {
+    frequency: f64,
+    t: KineticEnergy,
+}
+
+impl<T> LocalOperator<T> for QuantumDotHamiltonian
+where
+    T: Function<f64, D = Ix2> + Differentiate<D = Ix2>,
+{
+    fn act_on(&self, wf: &T, cfg: &Array2<f64>) -> Result<OperatorValue> {
+        let ke = self.t.act_on(wf, cfg)?;
+        let pe = OperatorValue::Scalar(
+            0.5 * self.frequency.powi(2) * cfg.norm_l2().powi(2) * wf.value(cfg)?,
+        );
+        &ke + &pe
+    }
+}
+
+// $\psi = e^{-r^2/a^2}$ in two dimensions
+#[derive(Clone)]
+struct GaussianWaveFunction {
+    params: Array1<f64>,
+}
+
+impl Function<f64> for GaussianWaveFunction {
+    type D = Ix2;
+
+    fn value(&self, x: &Array2<f64>) -> Result<f64> {
+        let a = self.params[0];
+        Ok(f64::exp(-(x.norm_l2() / a).powi(2)))
+    }
+}
+
+impl Differentiate for GaussianWaveFunction {
+    type D = Ix2;
+
+    fn gradient(&self, x: &Array2<f64>) -> Result<Array2<f64>> {
+        let a = self.params[0];
+        Ok(-2.0 * self.value(x)? / a.powi(2) * x)
+    }
+
+    fn laplacian(&self, x: &Array2<f64>) -> Result<f64> {
+        let a = self.params[0];
+        let dim = x.len_of(Axis(1)) as f64;
+        Ok(self.value(x)? * (4.0 * x.norm_l2().powi(2) - 2.0 * dim * a.powi(2)) / a.powi(4))
+    }
+}
+
+impl WaveFunction for GaussianWaveFunction {
+    fn num_electrons(&self) -> usize {
+        1
+    }
+
+    fn dimension(&self) -> usize {
+        2
+    }
+}
+
+impl Optimize for GaussianWaveFunction {
+    fn parameter_gradient(&self, cfg: &Array2<f64>) -> Result<Array1<f64>> {
+        let a = self.params[0];
+        Ok(array![
+            self.value(cfg)? * 2.0 * cfg.norm_l2().powi(2) / a.powi(3)
+        ])
+    }
+
+    fn update_parameters(&mut self, deltap: &Array1<f64>) {
+        self.params += deltap;
+    }
+
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
+    }
+
+    fn num_parameters(&self) -> usize {
+        self.params.len()
+    }
+}
+
+#[test]
+fn quantum_dot_2d() {
+    let wf = GaussianWaveFunction {
+        params: array![1.0],
+    };
+    let h = QuantumDotHamiltonian {
+        frequency: 1.0,
+        t: KineticEnergy::new(),
+    };
+
+    let metrop = MetropolisDiffuse::from_rng(0.1, StdRng::from_seed([0_u8; 32]));
+
+    let obs = operators! {
+        "Energy" => h,
+        "Parameter gradient" => ParameterGradient
+    };
+
+    let sampler = Sampler::new(wf, metrop, &obs).unwrap();
+
+    let vmc = VmcRunner::new(sampler, SteepestDescent::new(0.1), EmptyLogger);
+
+    let (wf, energies, errors) = vmc.run_optimization(20, 1000, 10, 4).unwrap();
+
+    // the ground state energy in two dimensions is $\omega$, at $a = \sqrt{2/\omega}$
+    let energy = energies.iter().last().unwrap();
+    let error = *errors.iter().last().unwrap();
+    assert!((energy - 1.0).abs() < error.max(1e-3));
+    assert!((wf.parameters()[0] - 2f64.sqrt()).abs() < 0.05);
+}
@@ -45,7 +45,7 @@ where
         let pe = OperatorValue::Scalar(
             0.5 * self.frequency.powi(2) * cfg.norm_l2().powi(2) * wf.value(cfg)?,
         );
-        Ok(&ke + &pe)
+        &ke + &pe
     }
 }
 
@@ -101,8 +101,8 @@ impl Optimize for GaussianWaveFunction {
         self.params += deltap;
     }
 
-    fn parameters(&self) -> &Array1<f64> {
-        &self.params
+    fn parameters(&self) -> Array1<f64> {
+        self.params.clone()
     }
 
     fn num_parameters(&self) -> usize {
@@ -130,7 +130,7 @@ fn sho_optimize() {
 
     let sampler = Sampler::new(wf, metrop, &obs).unwrap();
 
-    let vmc = VmcRunner::new(sampler, SteepestDescent::new(1e-3), EmptyLogger);
+    let vmc = VmcRunner::new(sampler, SteepestDescent::new(0.1), EmptyLogger);
 
     let (_, energies, errors) = vmc.run_optimization(ITERS, SAMPLES, BLOCK_SIZE, 4).unwrap();
 